@@ -25,7 +25,10 @@ async fn main() {
 
     let calendar_client = GCalClient::new(token, None).unwrap().calendar_client();
     let list = calendar_client
-        .list(true, CalendarAccessRole::Reader)
+        .list(Some(CalendarListOptions {
+            min_access_role: Some(MinAccessRole::Reader),
+            ..Default::default()
+        }))
         .await
         .unwrap();
 
@@ -37,7 +37,10 @@ async fn main() {
     let (calendar_client, event_client) = GCalClient::new(token, None).unwrap().clients();
 
     let list = calendar_client
-        .list(true, CalendarAccessRole::Reader)
+        .list(Some(CalendarListOptions {
+            min_access_role: Some(MinAccessRole::Reader),
+            ..Default::default()
+        }))
         .await
         .unwrap();
 
@@ -48,7 +51,14 @@ async fn main() {
     for calendar in list {
         event_list.extend(
             event_client
-                .list(calendar.id.clone(), start, end)
+                .list(
+                    calendar.id.clone(),
+                    Some(EventListOptions {
+                        time_min: Some(start),
+                        time_max: Some(end),
+                        ..Default::default()
+                    }),
+                )
                 .await
                 .unwrap(),
         );
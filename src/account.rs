@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use futures_util::{stream, StreamExt};
+use reqwest::ClientBuilder;
+use tokio::sync::RwLock;
+
+use super::{ClientError, ClientResult, GCalClient, OAuth, OToken, UserInfo};
+
+/// AccountManager holds the clients of many users, keyed by their Google user ID and reachable by
+/// email too. Clients are built on first use and share one connection pool. Each account's token
+/// is refreshed at most once at a time however many requests wait on it, and clients unused for
+/// the idle timeout are dropped while their tokens are kept.
+#[derive(Debug)]
+pub struct AccountManager {
+    http: reqwest::Client,
+    oauth: Option<Arc<OAuth>>,
+    base_url: Option<String>,
+    idle_timeout: Duration,
+    refresh_concurrency: usize,
+    accounts: Mutex<Accounts>,
+}
+
+#[derive(Debug)]
+struct Accounts {
+    by_id: HashMap<String, Account>,
+    /// Lowercase email to user ID.
+    emails: HashMap<String, String>,
+    last_sweep: Instant,
+}
+
+#[derive(Debug)]
+struct Account {
+    email: Option<String>,
+    /// Shared with the client, so it outlives its eviction.
+    token: Arc<RwLock<OToken>>,
+    client: Option<Arc<GCalClient>>,
+    last_used: Instant,
+}
+
+/// AccountManagerBuilder configures an AccountManager.
+#[derive(Debug)]
+pub struct AccountManagerBuilder {
+    oauth: Option<Arc<OAuth>>,
+    http: Option<reqwest::Client>,
+    base_url: Option<String>,
+    idle_timeout: Duration,
+    refresh_concurrency: usize,
+}
+
+impl AccountManagerBuilder {
+    /// Share this HTTP client instead of building one.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// See `GCalClientBuilder::base_url`.
+    pub fn base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Drop clients unused for this long, 30 minutes by default.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// How many tokens `AccountManager::refresh_all` refreshes at once, 8 by default.
+    pub fn refresh_concurrency(mut self, refresh_concurrency: usize) -> Self {
+        self.refresh_concurrency = refresh_concurrency.max(1);
+        self
+    }
+
+    pub fn build(self) -> ClientResult<AccountManager> {
+        let http = match self.http {
+            Some(http) => http,
+            None => ClientBuilder::new()
+                .gzip(true)
+                .https_only(self.base_url.is_none())
+                .build()?,
+        };
+
+        Ok(AccountManager {
+            http,
+            oauth: self.oauth,
+            base_url: self.base_url,
+            idle_timeout: self.idle_timeout,
+            refresh_concurrency: self.refresh_concurrency,
+            accounts: Mutex::new(Accounts {
+                by_id: HashMap::new(),
+                emails: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        })
+    }
+}
+
+impl AccountManager {
+    /// Manage accounts whose tokens are refreshed through `oauth`.
+    pub fn new(oauth: Arc<OAuth>) -> ClientResult<Self> {
+        Self::builder(Some(oauth)).build()
+    }
+
+    /// Configure a manager. Without OAuth configuration tokens are never refreshed.
+    pub fn builder(oauth: Option<Arc<OAuth>>) -> AccountManagerBuilder {
+        AccountManagerBuilder {
+            oauth,
+            http: None,
+            base_url: None,
+            idle_timeout: Duration::from_secs(30 * 60),
+            refresh_concurrency: 8,
+        }
+    }
+
+    /// Add the account `token` belongs to, looking up its user ID and email. Requires one of the
+    /// email, profile or openid scopes. Replaces the token of an account already added.
+    pub async fn add(&self, token: OToken) -> ClientResult<UserInfo> {
+        let token = Arc::new(RwLock::new(token));
+        let client = self.build_client(token.clone())?;
+        let info = client.user_info().await?;
+
+        let mut accounts = self.lock();
+        accounts.remove(&info.id);
+        accounts.insert(
+            info.id.clone(),
+            Account {
+                email: Some(info.email.clone()).filter(|e| !e.is_empty()),
+                token,
+                client: Some(client),
+                last_used: Instant::now(),
+            },
+        );
+        Ok(info)
+    }
+
+    /// Add an account whose user ID, and optionally email, are already known, without any
+    /// request. Its client is built on first use.
+    pub fn insert(&self, id: impl ToString, email: Option<String>, token: OToken) {
+        let id = id.to_string();
+        let mut accounts = self.lock();
+        accounts.remove(&id);
+        accounts.insert(
+            id,
+            Account {
+                email,
+                token: Arc::new(RwLock::new(token)),
+                client: None,
+                last_used: Instant::now(),
+            },
+        );
+    }
+
+    /// The client of an account by user ID or email, built when it has none.
+    pub fn client(&self, account: &str) -> ClientResult<Arc<GCalClient>> {
+        let mut accounts = self.lock();
+        let now = Instant::now();
+        if now.duration_since(accounts.last_sweep) >= self.idle_timeout {
+            accounts.evict_idle(self.idle_timeout);
+        }
+
+        let id = accounts.resolve(account)?;
+        let entry = accounts
+            .by_id
+            .get_mut(&id)
+            .ok_or_else(|| ClientError::UnknownAccount(account.to_string()))?;
+        entry.last_used = now;
+        if let Some(client) = &entry.client {
+            return Ok(client.clone());
+        }
+        let client = self.build_client(entry.token.clone())?;
+        entry.client = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Forget an account, returning its latest token.
+    pub async fn remove(&self, account: &str) -> Option<OToken> {
+        let entry = {
+            let mut accounts = self.lock();
+            let id = accounts.resolve(account).ok()?;
+            accounts.remove(&id)?
+        };
+        let token = entry.token.read().await.clone();
+        Some(token)
+    }
+
+    /// The user IDs of all accounts.
+    pub fn accounts(&self) -> Vec<String> {
+        self.lock().by_id.keys().cloned().collect()
+    }
+
+    /// A copy of an account's latest token, e.g. to persist it.
+    pub async fn token(&self, account: &str) -> ClientResult<OToken> {
+        let token = {
+            let accounts = self.lock();
+            let id = accounts.resolve(account)?;
+            accounts
+                .by_id
+                .get(&id)
+                .ok_or_else(|| ClientError::UnknownAccount(account.to_string()))?
+                .token
+                .clone()
+        };
+        let token = token.read().await.clone();
+        Ok(token)
+    }
+
+    /// Drop the clients unused for the idle timeout, returning how many. This also happens on
+    /// `client` once per idle timeout.
+    pub fn evict_idle(&self) -> usize {
+        self.lock().evict_idle(self.idle_timeout)
+    }
+
+    /// Refresh the expired tokens of all accounts with a client, a few at a time. Returns the
+    /// user IDs that failed with their errors.
+    pub async fn refresh_all(&self) -> Vec<(String, ClientError)> {
+        let clients: Vec<_> = self
+            .lock()
+            .by_id
+            .iter()
+            .filter_map(|(id, a)| Some((id.clone(), a.client.clone()?)))
+            .collect();
+
+        stream::iter(clients)
+            .map(|(id, client)| async move { (id, client.refresh().await) })
+            .buffer_unordered(self.refresh_concurrency)
+            .filter_map(|(id, result)| async move { result.err().map(|e| (id, e)) })
+            .collect()
+            .await
+    }
+
+    fn build_client(&self, token: Arc<RwLock<OToken>>) -> ClientResult<Arc<GCalClient>> {
+        let mut builder = GCalClient::shared_builder(token).http_client(self.http.clone());
+        if let Some(oauth) = &self.oauth {
+            builder = builder.oauth(oauth.clone());
+        }
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        builder.build()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Accounts> {
+        self.accounts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Accounts {
+    /// The user ID of an account given by user ID or email. Emails match case-insensitively.
+    fn resolve(&self, account: &str) -> ClientResult<String> {
+        if self.by_id.contains_key(account) {
+            return Ok(account.to_string());
+        }
+        self.emails
+            .get(&account.to_lowercase())
+            .cloned()
+            .ok_or_else(|| ClientError::UnknownAccount(account.to_string()))
+    }
+
+    fn insert(&mut self, id: String, account: Account) {
+        if let Some(email) = &account.email {
+            self.emails.insert(email.to_lowercase(), id.clone());
+        }
+        self.by_id.insert(id, account);
+    }
+
+    fn remove(&mut self, id: &str) -> Option<Account> {
+        let account = self.by_id.remove(id)?;
+        if let Some(email) = &account.email {
+            self.emails.remove(&email.to_lowercase());
+        }
+        Some(account)
+    }
+
+    fn evict_idle(&mut self, idle_timeout: Duration) -> usize {
+        let now = Instant::now();
+        self.last_sweep = now;
+        let mut evicted = 0;
+        for account in self.by_id.values_mut() {
+            if account.client.is_some() && now.duration_since(account.last_used) >= idle_timeout {
+                account.client = None;
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+}
@@ -1,33 +1,50 @@
 use std::sync::Arc;
 
-use super::{CalendarAccessRole, CalendarList, CalendarListItem, ClientResult, GCalClient, CalendarListOptions, MinAccessRole};
+use super::{options_query, CalendarAccessRole, CalendarList, CalendarListItem, ClientResult, GCalClient, CalendarListOptions, MinAccessRole, ParseAccessRoleError};
 
 
 // Implementation for string conversion
-impl From<&str> for MinAccessRole {
-    fn from(s: &str) -> Self {
+impl std::str::FromStr for MinAccessRole {
+    type Err = ParseAccessRoleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "freebusyreader" => MinAccessRole::FreeBusyReader,
-            "owner" => MinAccessRole::Owner,
-            "reader" => MinAccessRole::Reader,
-            "writer" => MinAccessRole::Writer,
-            _ => panic!("Invalid access role"),  // Or handle error differently
+            "freebusyreader" => Ok(MinAccessRole::FreeBusyReader),
+            "owner" => Ok(MinAccessRole::Owner),
+            "reader" => Ok(MinAccessRole::Reader),
+            "writer" => Ok(MinAccessRole::Writer),
+            _ => Err(ParseAccessRoleError(s.to_string())),
         }
     }
 }
 
-// Optional: Implementation for serialization
-impl ToString for MinAccessRole {
-    fn to_string(&self) -> String {
-        match self {
-            MinAccessRole::FreeBusyReader => "freeBusyReader".to_string(),
-            MinAccessRole::Owner => "owner".to_string(),
-            MinAccessRole::Reader => "reader".to_string(),
-            MinAccessRole::Writer => "writer".to_string(),
+// Unlike deserialization, parsing only accepts the roles this crate knows about.
+impl std::str::FromStr for CalendarAccessRole {
+    type Err = ParseAccessRoleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<MinAccessRole>()? {
+            MinAccessRole::FreeBusyReader => Ok(CalendarAccessRole::FreeBusyReader),
+            MinAccessRole::Owner => Ok(CalendarAccessRole::Owner),
+            MinAccessRole::Reader => Ok(CalendarAccessRole::Reader),
+            MinAccessRole::Writer => Ok(CalendarAccessRole::Writer),
         }
     }
 }
 
+// Optional: Implementation for serialization
+impl std::fmt::Display for MinAccessRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            MinAccessRole::FreeBusyReader => "freeBusyReader",
+            MinAccessRole::Owner => "owner",
+            MinAccessRole::Reader => "reader",
+            MinAccessRole::Writer => "writer",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 /// CalendarListClient is the method of accessing the calendar list. You must provide it with a
 /// Google Calendar client.
 #[derive(Debug, Clone)]
@@ -47,8 +64,7 @@ impl CalendarListClient {
         
         if let Some(opts) = options {
             // Convert the options to query parameters automatically
-            let query_params = serde_qs::to_string(&opts)?;
-            for (key, value) in serde_qs::from_str::<Vec<(String, String)>>(&query_params)? {
+            for (key, value) in options_query(&opts)? {
                 cl.add_query(key, value);
             }
         }
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
-    CalendarAccessRole, ConferenceProperties, DefaultReminder, NotificationSettings, QueryParams,
-    Sendable,
+    scope, set_query, CalendarAccessRole, CalendarScope, ConferenceProperties, DefaultReminder,
+    NotificationSettings, QueryParams, Sendable,
 };
 
 /* Google Calendar API: https://developers.google.com/calendar/api/v3/reference/calendarList#resource */
@@ -69,7 +69,7 @@ pub struct CalendarList {
 }
 impl CalendarList {
     pub fn add_query(&mut self, key: String, value: String) {
-        self.query_string.insert(key, value);
+        set_query(&mut self.query_string, key, value);
     }
 }
 
@@ -81,6 +81,14 @@ impl Sendable for CalendarListItem {
     fn query(&self) -> QueryParams {
         self.query_string.clone()
     }
+
+    fn scopes(&self, write: bool) -> &'static [CalendarScope] {
+        if write {
+            scope::CALENDAR_LIST_WRITE
+        } else {
+            scope::CALENDAR_LIST_READ
+        }
+    }
 }
 impl Sendable for CalendarList {
     fn path(&self, _action: Option<String>) -> String {
@@ -90,6 +98,14 @@ impl Sendable for CalendarList {
     fn query(&self) -> QueryParams {
         self.query_string.clone()
     }
+
+    fn scopes(&self, write: bool) -> &'static [CalendarScope] {
+        if write {
+            scope::CALENDAR_LIST_WRITE
+        } else {
+            scope::CALENDAR_LIST_READ
+        }
+    }
 }
 
 fn default_entry_kind() -> Option<String> {
@@ -41,6 +41,14 @@ impl Sendable for Calendar {
     fn query(&self) -> QueryParams {
         Default::default()
     }
+
+    fn scopes(&self, write: bool) -> &'static [CalendarScope] {
+        if write {
+            scope::CALENDARS_WRITE
+        } else {
+            scope::CALENDARS_READ
+        }
+    }
 }
 fn default_kind() -> Option<String> {
     Some("calendar#calendar".to_string())
@@ -1,27 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum CalendarAccessRole {
-    #[default]
-    Owner,
-    Reader,
-    Writer,
-    FreeBusyReader,
-}
-impl CalendarAccessRole {
-    pub fn to_str(&self) -> &'static str {
-        match self {
-            Self::Owner => "owner",
-            Self::Reader => "reader",
-            Self::Writer => "writer",
-            Self::FreeBusyReader => "freeBusyReader",
-        }
-    }
-}
-impl std::fmt::Display for CalendarAccessRole {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_str())
+api_enum! {
+    pub enum CalendarAccessRole {
+        #[default]
+        Owner => "owner",
+        Reader => "reader",
+        Writer => "writer",
+        FreeBusyReader => "freeBusyReader",
     }
 }
 
@@ -40,23 +25,22 @@ pub struct NotificationSetting {
     pub typ: NotificationSettingType,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum NotificationSettingMethod {
-    #[serde(rename = "email")]
-    #[default]
-    EMail,
+api_enum! {
+    pub enum NotificationSettingMethod {
+        #[default]
+        EMail => "email",
+    }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum NotificationSettingType {
-    #[default]
-    EventCreation,
-    EventChange,
-    EventCancellation,
-    EventResponse,
-    Agenda,
+api_enum! {
+    pub enum NotificationSettingType {
+        #[default]
+        EventCreation => "eventCreation",
+        EventChange => "eventChange",
+        EventCancellation => "eventCancellation",
+        EventResponse => "eventResponse",
+        Agenda => "agenda",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -76,21 +60,19 @@ pub struct DefaultReminder {
     pub minutes: u16,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum ReminderMethod {
-    #[serde(rename = "email")]
-    EMail,
-    #[serde(rename = "popup")]
-    #[default]
-    PopUp,
+api_enum! {
+    pub enum ReminderMethod {
+        EMail => "email",
+        #[default]
+        PopUp => "popup",
+    }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum AllowedSolutionType {
-    #[default]
-    EventHangout,
-    EventNamedHangout,
-    HangoutsMeet,
+api_enum! {
+    pub enum AllowedSolutionType {
+        #[default]
+        EventHangout => "eventHangout",
+        EventNamedHangout => "eventNamedHangout",
+        HangoutsMeet => "hangoutsMeet",
+    }
 }
\ No newline at end of file
@@ -4,9 +4,14 @@ use reqwest::{
     header::{HeaderMap, HeaderValue},
     ClientBuilder, RequestBuilder, Response,
 };
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock, RwLockWriteGuard};
 
-use super::{CalendarListClient, ClientError, ClientResult, EventClient, OAuth, OToken, Sendable};
+const GOOGLE_APIS_URL: &str = "https://www.googleapis.com";
+
+use super::{
+    CalendarListClient, ClientError, ClientResult, EventClient, OAuth, OToken, Sendable,
+    ServiceAccount, TokenStore, BASE_URL,
+};
 
 /// Client is a Google Calendar client. The access key must have already been fetched and the oauth
 /// negotiation should have already been completed. The client itself only implements HTTP verbs
@@ -17,23 +22,157 @@ pub struct GCalClient {
     client: reqwest::Client,
     headers: Option<HeaderMap<HeaderValue>>,
     token: Arc<RwLock<OToken>>,
-    oauth: Option<Arc<OAuth>>,
+    auth: Option<Authenticator>,
+    store: Option<Arc<dyn TokenStore>>,
+    /// Held while calling the store, so that saves land in the order of the renewals.
+    store_order: Arc<Mutex<()>>,
+    base_url: Option<String>,
+
+    debug: bool,
+}
+
+/// Authenticator renews the access token of a client once it has expired.
+#[derive(Debug, Clone)]
+pub enum Authenticator {
+    /// Refresh through the user's refresh token.
+    OAuth(Arc<OAuth>),
+    /// Exchange a new assertion signed by the service account.
+    ServiceAccount(Arc<ServiceAccount>),
+}
+
+impl Authenticator {
+    /// Whether `token` has to be renewed before it is used.
+    pub fn needs_refresh(&self, token: &OToken) -> bool {
+        match self {
+            Self::OAuth(_) => token.is_expired(),
+            Self::ServiceAccount(_) => token.access.is_empty() || token.is_expired(),
+        }
+    }
 
+    /// Renew `token` when it has expired.
+    pub async fn refresh(&self, token: &mut OToken) -> ClientResult<()> {
+        if self.needs_refresh(token) {
+            self.renew(token).await?;
+        }
+        Ok(())
+    }
+
+    /// Renew `token` now, e.g. after it was rejected before its expiry.
+    pub async fn renew(&self, token: &mut OToken) -> ClientResult<()> {
+        match self {
+            Self::OAuth(oauth) => Ok(oauth.renew(token).await?),
+            Self::ServiceAccount(account) => account.renew(token).await,
+        }
+    }
+}
+
+/// GCalClientBuilder configures a GCalClient beyond what `GCalClient::new` offers.
+#[derive(Debug)]
+pub struct GCalClientBuilder {
+    token: Arc<RwLock<OToken>>,
+    http: Option<reqwest::Client>,
+    auth: Option<Authenticator>,
+    store: Option<Arc<dyn TokenStore>>,
+    base_url: Option<String>,
     debug: bool,
 }
 
+impl GCalClientBuilder {
+    fn with_oauth(mut self, oauth: Option<Arc<OAuth>>) -> Self {
+        self.auth = oauth.map(Authenticator::OAuth);
+        self
+    }
+
+    /// Refresh the token through this OAuth configuration when it expires.
+    pub fn oauth(mut self, oauth: Arc<OAuth>) -> Self {
+        self.auth = Some(Authenticator::OAuth(oauth));
+        self
+    }
+
+    /// Authenticate as a service account. The token given to the builder may be empty, one is
+    /// fetched before the first request.
+    pub fn service_account(mut self, account: Arc<ServiceAccount>) -> Self {
+        self.auth = Some(Authenticator::ServiceAccount(account));
+        self
+    }
+
+    /// Save the token to `store` whenever it was refreshed.
+    pub fn token_store(mut self, store: Arc<dyn TokenStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Send requests through this client, sharing its connection pool with other clients. It is
+    /// used as is, `base_url` doesn't relax its settings.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// Send requests to another server instead of the Google Calendar API, e.g. a local mock.
+    /// Other Google APIs, such as the user info, are sent to their path below the base URL.
+    /// Plain HTTP is only allowed when a base URL is set.
+    pub fn base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Print every request to stderr.
+    pub fn debug(mut self) -> Self {
+        self.debug = true;
+        self
+    }
+
+    pub fn build(self) -> ClientResult<Arc<GCalClient>> {
+        let client = match self.http {
+            Some(http) => http,
+            None => ClientBuilder::new()
+                .gzip(true)
+                .https_only(self.base_url.is_none())
+                .build()?,
+        };
+
+        Ok(Arc::new(GCalClient {
+            client,
+            headers: None,
+            token: self.token,
+            auth: self.auth,
+            store: self.store,
+            store_order: Default::default(),
+            base_url: self.base_url,
+            debug: self.debug,
+        }))
+    }
+}
+
 impl GCalClient {
     /// Create a new client. Requires an access key.
     pub fn new(token: OToken, oauth: Option<Arc<OAuth>>) -> ClientResult<Arc<Self>> {
-        let client = ClientBuilder::new().gzip(true).https_only(true).build()?;
+        Self::builder(token).with_oauth(oauth).build()
+    }
 
-        Ok(Arc::new(Self {
-            client,
-            headers: None,
-            token: Arc::new(token.into()),
-            oauth,
+    /// Create a client authenticated as a service account.
+    pub fn from_service_account(account: ServiceAccount) -> ClientResult<Arc<Self>> {
+        Self::builder(OToken::default())
+            .service_account(Arc::new(account))
+            .build()
+    }
+
+    /// Configure a new client. Requires an access key.
+    pub fn builder(token: OToken) -> GCalClientBuilder {
+        Self::shared_builder(Arc::new(token.into()))
+    }
+
+    /// Configure a client using `token` in place, shared with its other users.
+    pub(crate) fn shared_builder(token: Arc<RwLock<OToken>>) -> GCalClientBuilder {
+        GCalClientBuilder {
+            token,
+            http: None,
+            auth: None,
+            store: None,
+            base_url: None,
             debug: false,
-        }))
+        }
     }
     pub fn calendar_client(self: Arc<Self>) -> CalendarListClient {
         CalendarListClient::new(self.clone())
@@ -52,12 +191,105 @@ impl GCalClient {
         self.debug = true
     }
 
+    /// A copy of the current token.
+    pub async fn token(&self) -> OToken {
+        self.token.read().await.clone()
+    }
+
+    /// Renew the token now when it has expired, instead of before the next request. Refreshed
+    /// tokens are saved to the token store.
+    pub async fn refresh(&self) -> ClientResult<()> {
+        let Some(auth) = &self.auth else {
+            return Ok(());
+        };
+        // Requests only share a read lock while the token is valid.
+        if !auth.needs_refresh(&*self.token.read().await) {
+            return Ok(());
+        }
+
+        let mut token = self.token.write().await;
+        // Another request may have refreshed it while this one waited for the lock.
+        if auth.needs_refresh(&token) {
+            auth.renew(&mut token).await?;
+            self.save(token).await?;
+        }
+        Ok(())
+    }
+
+    /// Revoke the token and forget it, e.g. for a "disconnect Google account" button. The token
+    /// is forgotten even when revoking fails, e.g. while Google is unavailable. The revoke error
+    /// is still returned; keep `token` beforehand to retry with `OAuth::revoke`. Requires the
+    /// OAuth configuration. Requests fail afterwards.
+    pub async fn sign_out(&self) -> ClientResult<()> {
+        let Some(Authenticator::OAuth(oauth)) = &self.auth else {
+            return Err(ClientError::AuthError(
+                "signing out requires the OAuth configuration".to_string(),
+            ));
+        };
+        let token = self.token.write().await;
+        let revoked = oauth.revoke(&token).await;
+        self.forget_token(token).await?;
+        Ok(revoked?)
+    }
+
+    /// Clear the token store and the token without revoking it at Google. Requests fail
+    /// afterwards.
+    pub async fn forget(&self) -> ClientResult<()> {
+        self.forget_token(self.token.write().await).await
+    }
+
+    async fn forget_token(&self, mut token: RwLockWriteGuard<'_, OToken>) -> ClientResult<()> {
+        *token = OToken::default();
+        self.with_store(token, |store| store.clear()).await
+    }
+
+    /// Renew the token after `rejected` was refused, unless another request already did.
+    /// Returns false when the token cannot be renewed.
+    async fn renew_rejected(&self, rejected: &str) -> ClientResult<bool> {
+        let Some(auth) = &self.auth else {
+            return Ok(false);
+        };
+        let mut token = self.token.write().await;
+        if token.access == rejected {
+            auth.renew(&mut token).await?;
+            self.save(token).await?;
+        }
+        Ok(true)
+    }
+
+    async fn save(&self, token: RwLockWriteGuard<'_, OToken>) -> ClientResult<()> {
+        let saved = token.clone();
+        self.with_store(token, move |store| store.save(&saved))
+            .await
+    }
+
+    /// Call the token store once `token` is unlocked, so that requests waiting for it go on.
+    /// Stores write files and may derive keys, so the call runs on the blocking thread pool.
+    async fn with_store(
+        &self,
+        token: RwLockWriteGuard<'_, OToken>,
+        call: impl FnOnce(&dyn TokenStore) -> ClientResult<()> + Send + 'static,
+    ) -> ClientResult<()> {
+        let Some(store) = self.store.clone() else {
+            return Ok(());
+        };
+        let order = self.store_order.clone().lock_owned().await;
+        drop(token);
+        tokio::task::spawn_blocking(move || {
+            let _order = order;
+            call(store.as_ref())
+        })
+        .await
+        .map_err(|e| ClientError::TokenStoreError(e.to_string()))?
+    }
+
     /// Perform a GET request.
     pub async fn get(
         &self,
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
+        self.check_scopes(&target, false).await?;
         self.send(self.client.get(self.get_url("GET", &target, action)?))
             .await
     }
@@ -68,6 +300,7 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
+        self.check_scopes(&target, true).await?;
         self.send(
             self.client
                 .post(self.get_url("POST", &target, action)?)
@@ -82,6 +315,7 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
+        self.check_scopes(&target, true).await?;
         self.send(
             self.client
                 .put(self.get_url("PUT", &target, action)?)
@@ -96,6 +330,7 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
+        self.check_scopes(&target, true).await?;
         self.send(
             self.client
                 .patch(self.get_url("PATCH", &target, action)?)
@@ -110,39 +345,73 @@ impl GCalClient {
         action: Option<String>,
         target: impl Sendable,
     ) -> ClientResult<Response> {
+        self.check_scopes(&target, true).await?;
         self.send(self.client.delete(self.get_url("DELETE", &target, action)?))
             .await
     }
 
-    async fn send(&self, mut req: RequestBuilder) -> ClientResult<Response> {
-        if let Some(oauth) = &self.oauth {
-            oauth.refresh(&mut *(self.token.write().await)).await?;
+    /// Fail early when the token's granted scopes are known and none of them allows the request.
+    async fn check_scopes(&self, target: &impl Sendable, write: bool) -> ClientResult<()> {
+        let required = target.scopes(write);
+        match &self.token.read().await.scopes {
+            Some(granted) if !required.is_empty() && !granted.contains_any(required) => {
+                Err(ClientError::InsufficientScope {
+                    required: required.into(),
+                    granted: granted.clone(),
+                })
+            }
+            _ => Ok(()),
         }
+    }
+
+    async fn send(&self, mut req: RequestBuilder) -> ClientResult<Response> {
+        self.refresh().await?;
         if let Some(headers) = &self.headers {
             req = req.headers(headers.clone())
         }
 
-        let resp = self.set_bearer(req).await.send().await?;
-        if resp.status() != 200 {
-            if let Some(header) = resp.headers().get("WWW-Authenticate") {
-                if header
-                    .to_str()?
-                    .starts_with(r#"Bearer error="invalid_token""#)
-                {
-                    return Err(ClientError::InvalidToken);
-                }
+        // A token can be revoked or rotated before it expires. Renew it and retry once.
+        let retry = req.try_clone();
+        let (resp, access) = self.send_once(req).await?;
+        let resp = match retry {
+            Some(retry) if is_invalid_token(&resp) && self.renew_rejected(&access).await? => {
+                self.send_once(retry).await?.0
             }
+            _ => resp,
+        };
+
+        if is_invalid_token(&resp) {
+            return Err(ClientError::InvalidToken);
+        }
+        if !resp.status().is_success() {
+            return Err(ClientError::from_response(resp).await);
         }
         Ok(resp)
     }
 
+    /// Send `req` with the current token, returning the response and the access token used.
+    async fn send_once(&self, req: RequestBuilder) -> ClientResult<(Response, String)> {
+        let access = self.token.read().await.access.clone();
+        let resp = req
+            .header("Authorization", format!("Bearer {}", access))
+            .send()
+            .await?;
+        Ok((resp, access))
+    }
+
     fn get_url(
         &self,
         method: &str,
         target: &impl Sendable,
         action: Option<String>,
     ) -> ClientResult<url::Url> {
-        let url = target.url(action)?;
+        let mut url = target.url(action)?;
+        if let Some(base) = &self.base_url {
+            url = url::Url::parse(&match url.as_str().strip_prefix(BASE_URL) {
+                Some(path) => format!("{}{}", base, path),
+                None => url.as_str().replacen(GOOGLE_APIS_URL, base, 1),
+            })?;
+        }
 
         if self.debug {
             eprintln!(
@@ -155,11 +424,14 @@ impl GCalClient {
 
         Ok(url)
     }
+}
 
-    async fn set_bearer(&self, req: RequestBuilder) -> RequestBuilder {
-        req.header(
-            "Authorization",
-            format!("Bearer {}", self.token.read().await.access),
-        )
-    }
+/// Whether Google rejected the access token, rather than the request.
+fn is_invalid_token(resp: &Response) -> bool {
+    !resp.status().is_success()
+        && resp
+            .headers()
+            .get("WWW-Authenticate")
+            .and_then(|h| h.to_str().ok())
+            .is_some_and(|h| h.starts_with("Bearer") && h.contains(r#"error="invalid_token""#))
 }
@@ -0,0 +1,240 @@
+use std::{
+    fmt::Debug,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    token_store::{replace_private, store_error},
+    ClientError, ClientResult, OToken, TokenStore,
+};
+
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+
+/// TokenKey is a named key sealing the tokens of an `EncryptedFileTokenStore`. Its id is saved
+/// next to the ciphertext, so the right key is found again after rotating to a new one.
+#[derive(Clone)]
+pub struct TokenKey {
+    id: String,
+    secret: Secret,
+}
+
+#[derive(Clone)]
+enum Secret {
+    Key([u8; 32]),
+    Passphrase(String),
+}
+
+impl Debug for TokenKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenKey {
+    /// A 256 bit key, e.g. from a secret manager.
+    pub fn new(id: impl ToString, key: [u8; 32]) -> Self {
+        Self {
+            id: id.to_string(),
+            secret: Secret::Key(key),
+        }
+    }
+
+    /// A key derived from `passphrase` with Argon2id, salted anew for every file written.
+    pub fn from_passphrase(id: impl ToString, passphrase: impl ToString) -> Self {
+        Self {
+            id: id.to_string(),
+            secret: Secret::Passphrase(passphrase.to_string()),
+        }
+    }
+
+    /// A new random key. Keep its bytes, see `bytes`, or the tokens are lost with it.
+    pub fn generate(id: impl ToString) -> Self {
+        Self::new(id, XChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The raw key, `None` for passphrases.
+    pub fn bytes(&self) -> Option<&[u8; 32]> {
+        match &self.secret {
+            Secret::Key(key) => Some(key),
+            Secret::Passphrase(_) => None,
+        }
+    }
+
+    fn cipher(&self, salt: Option<&[u8]>) -> Result<XChaCha20Poly1305, String> {
+        match (&self.secret, salt) {
+            (Secret::Key(key), _) => Ok(XChaCha20Poly1305::new(Key::from_slice(key))),
+            (Secret::Passphrase(passphrase), Some(salt)) => {
+                let mut key = [0u8; 32];
+                Argon2::default()
+                    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                    .map_err(|e| e.to_string())?;
+                Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+            }
+            (Secret::Passphrase(_), None) => Err("missing the passphrase salt".to_string()),
+        }
+    }
+}
+
+/// The file written by `EncryptedFileTokenStore`.
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    version: u8,
+    key_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// The version and key id are authenticated along with the token, so neither can be swapped.
+fn associated_data(key_id: &str) -> Vec<u8> {
+    format!("gcal_rs token v{} {}", VERSION, key_id).into_bytes()
+}
+
+/// EncryptedFileTokenStore keeps the token sealed with XChaCha20-Poly1305 in a file only readable
+/// by the current user. Files that were modified fail to load.
+///
+/// To rotate keys, create the store with the new key and add the previous ones with `old_key`.
+/// Tokens sealed with an old key still load, and are sealed with the new key on their next save
+/// or by `rotate`.
+#[derive(Debug, Clone)]
+pub struct EncryptedFileTokenStore {
+    path: PathBuf,
+    key: TokenKey,
+    old_keys: Vec<TokenKey>,
+}
+
+impl EncryptedFileTokenStore {
+    pub fn new(path: impl Into<PathBuf>, key: TokenKey) -> Self {
+        Self {
+            path: path.into(),
+            key,
+            old_keys: Vec::new(),
+        }
+    }
+
+    /// Also open tokens sealed with this previous key.
+    pub fn old_key(mut self, key: TokenKey) -> Self {
+        self.old_keys.push(key);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Seal the saved token with the current key when an old one was used. Returns whether the
+    /// file was rewritten.
+    pub fn rotate(&self) -> ClientResult<bool> {
+        match self.open()? {
+            Some((token, key_id)) if key_id != self.key.id => {
+                self.save(&token)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn error(&self, action: &str, e: impl std::fmt::Display) -> ClientError {
+        store_error(action, &self.path, e)
+    }
+
+    /// The saved token and the id of the key it was sealed with.
+    fn open(&self) -> ClientResult<Option<(OToken, String)>> {
+        let json = match fs::read(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(self.error("read", e)),
+        };
+        let sealed: Sealed = serde_json::from_slice(&json).map_err(|e| self.error("parse", e))?;
+        if sealed.version != VERSION {
+            return Err(self.error("open", format!("unsupported version {}", sealed.version)));
+        }
+        let key = std::iter::once(&self.key)
+            .chain(&self.old_keys)
+            .find(|k| k.id == sealed.key_id)
+            .ok_or_else(|| self.error("open", format!("unknown key `{}`", sealed.key_id)))?;
+
+        let decode = |field: &str| STANDARD.decode(field).map_err(|e| self.error("parse", e));
+        let salt = sealed.salt.as_deref().map(decode).transpose()?;
+        let nonce = decode(&sealed.nonce)?;
+        if nonce.len() != 24 {
+            return Err(self.error("parse", "invalid nonce"));
+        }
+        let plaintext = key
+            .cipher(salt.as_deref())
+            .map_err(|e| self.error("open", e))?
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &decode(&sealed.ciphertext)?,
+                    aad: &associated_data(&sealed.key_id),
+                },
+            )
+            .map_err(|_| self.error("open", "the file was modified or the key is wrong"))?;
+
+        let token = serde_json::from_slice(&plaintext).map_err(|e| self.error("parse", e))?;
+        Ok(Some((token, sealed.key_id)))
+    }
+}
+
+impl TokenStore for EncryptedFileTokenStore {
+    fn load(&self) -> ClientResult<Option<OToken>> {
+        Ok(self.open()?.map(|(token, _)| token))
+    }
+
+    fn save(&self, token: &OToken) -> ClientResult<()> {
+        let salt = matches!(self.key.secret, Secret::Passphrase(_)).then(|| {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            salt
+        });
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .key
+            .cipher(salt.as_ref().map(|s| s.as_slice()))
+            .map_err(|e| self.error("seal", e))?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &serde_json::to_vec(token)?,
+                    aad: &associated_data(&self.key.id),
+                },
+            )
+            .map_err(|e| self.error("seal", e))?;
+
+        let sealed = Sealed {
+            version: VERSION,
+            key_id: self.key.id.clone(),
+            salt: salt.map(|s| STANDARD.encode(s)),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        replace_private(&self.path, &serde_json::to_vec_pretty(&sealed)?)
+            .map_err(|e| self.error("write", e))
+    }
+
+    fn clear(&self) -> ClientResult<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(self.error("remove", e)),
+            _ => Ok(()),
+        }
+    }
+}
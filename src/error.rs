@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use super::CalendarScopes;
+
 pub type ClientResult<T, E = ClientError> = std::result::Result<T, E>;
 
 /// ClientError provides a mechanism to determine when the access token has expired. All other
@@ -8,12 +10,103 @@ pub type ClientResult<T, E = ClientError> = std::result::Result<T, E>;
 pub enum ClientError {
     #[error("Invalid Access Token")]
     InvalidToken,
+    #[error("Authentication Failed: {0}")]
+    AuthError(String),
     #[error("HTTP Error: {0}")]
     HttpError(reqwest::Error),
+    #[error("API Error ({status}): {message}")]
+    ApiError {
+        status: u16,
+        reason: Option<String>,
+        message: String,
+    },
+    #[error(transparent)]
+    InvalidAccessRole(#[from] ParseAccessRoleError),
+    #[error(transparent)]
+    InvalidEvent(#[from] EventValidationError),
+    #[error("Insufficient Scope: one of `{required}` is needed, granted `{granted}`")]
+    InsufficientScope {
+        required: CalendarScopes,
+        granted: CalendarScopes,
+    },
+    #[error("Not an attendee of event `{0}`")]
+    NotAnAttendee(String),
+    #[error("Conference Failed: {0}")]
+    ConferenceFailed(String),
+    #[error("Invalid Recurrence: {0}")]
+    InvalidRecurrence(String),
+    #[error("Unknown account `{0}`")]
+    UnknownAccount(String),
+    #[error("Token Store Error: {0}")]
+    TokenStoreError(String),
     #[error("Unknown Error: {0}")]
     UnknownError(String),
 }
 
+/// ParseAccessRoleError is returned when parsing a string that is not a known access role.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Invalid access role `{0}`")]
+pub struct ParseAccessRoleError(pub String);
+
+/// EventValidationError is returned when an event is checked locally before it is sent.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EventValidationError {
+    #[error("Event has no start")]
+    MissingStart,
+    #[error("Event has no end")]
+    MissingEnd,
+    #[error("Invalid event date `{0}`")]
+    InvalidDate(String),
+    #[error("All-day events need a date on both start and end")]
+    MixedAllDay,
+    #[error("Recurring events need a time zone on start and end")]
+    MissingTimeZone,
+    #[error("Event ends at `{end}`, not after its start `{start}`")]
+    EndNotAfterStart { start: String, end: String },
+    #[error("At most 5 reminder overrides are allowed, got {0}")]
+    TooManyReminders(usize),
+    #[error("Reminder of {0} minutes is outside 0 to 40320 minutes")]
+    ReminderOutOfRange(u16),
+    #[error("Invalid attendee email `{0}`")]
+    InvalidEmail(String),
+}
+
+impl ClientError {
+    /// Build an ApiError from a failed response, using the error object Google sends when the
+    /// body has one.
+    pub(crate) async fn from_response(resp: reqwest::Response) -> Self {
+        #[derive(serde::Deserialize, Default)]
+        #[serde(default)]
+        struct Body {
+            error: Detail,
+        }
+        #[derive(serde::Deserialize, Default)]
+        #[serde(default)]
+        struct Detail {
+            message: String,
+            errors: Vec<Reason>,
+        }
+        #[derive(serde::Deserialize, Default)]
+        #[serde(default)]
+        struct Reason {
+            reason: String,
+        }
+
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        let detail = serde_json::from_str::<Body>(&text).unwrap_or_default().error;
+        Self::ApiError {
+            status: status.as_u16(),
+            reason: detail.errors.into_iter().next().map(|e| e.reason),
+            message: if detail.message.is_empty() {
+                status.canonical_reason().unwrap_or_default().to_string()
+            } else {
+                detail.message
+            },
+        }
+    }
+}
+
 impl From<anyhow::Error> for ClientError {
     fn from(value: anyhow::Error) -> Self {
         Self::UnknownError(value.to_string())
@@ -0,0 +1,84 @@
+use super::{ClientResult, Event, EventAttachment, EventClient, EventRequestOptions};
+
+impl EventAttachment {
+    /// Attach a Google Drive file by its URL, e.g. `https://drive.google.com/file/d/<id>/view`.
+    /// Google fills in the other fields when the event is saved.
+    pub fn from_drive_url(file_url: impl ToString) -> Self {
+        Self {
+            file_url: file_url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_title(mut self, title: impl ToString) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn with_mime_type(mut self, mime_type: impl ToString) -> Self {
+        self.mime_type = Some(mime_type.to_string());
+        self
+    }
+
+    /// The Drive file ID, taken from the URL when Google hasn't provided it yet.
+    pub fn drive_file_id(&self) -> Option<&str> {
+        if let Some(id) = &self.file_id {
+            return Some(id);
+        }
+        let url = &self.file_url;
+        let id = match url.find("/d/") {
+            Some(idx) => &url[idx + 3..],
+            None => url.split(['?', '&']).find_map(|p| p.strip_prefix("id="))?,
+        };
+        let id = id.split(['/', '?', '&', '#']).next()?;
+        (!id.is_empty()).then_some(id)
+    }
+
+    /// Whether this is the file with the given URL or Drive file ID.
+    fn is_file(&self, file: &str) -> bool {
+        self.file_url == file || self.drive_file_id() == Some(file)
+    }
+}
+
+impl Event {
+    /// Attach a file, returns false when it is already attached. Events can carry at most 25
+    /// attachments.
+    pub fn add_attachment(&mut self, attachment: EventAttachment) -> bool {
+        let file = attachment
+            .drive_file_id()
+            .unwrap_or(&attachment.file_url)
+            .to_string();
+        if self.attachments.iter().any(|a| a.is_file(&file)) {
+            return false;
+        }
+        self.attachments.push(attachment);
+        true
+    }
+
+    /// Remove the attachment with the given file URL or Drive file ID.
+    pub fn remove_attachment(&mut self, file: &str) -> Option<EventAttachment> {
+        let idx = self.attachments.iter().position(|a| a.is_file(file))?;
+        Some(self.attachments.remove(idx))
+    }
+}
+
+impl EventClient {
+    /// Save the attachments of an event, e.g. after `Event::add_attachment` or
+    /// `Event::remove_attachment`. Only the attachments are sent, other fields are untouched.
+    pub async fn update_attachments(
+        &self,
+        event: &Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let options = EventRequestOptions {
+            supports_attachments: Some(true),
+            ..options.unwrap_or_default()
+        };
+        let mut fields = serde_json::Map::new();
+        fields.insert(
+            "attachments".to_string(),
+            serde_json::to_value(&event.attachments)?,
+        );
+        self.patch_fields(event, fields, Some(options)).await
+    }
+}
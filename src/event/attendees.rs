@@ -0,0 +1,159 @@
+use super::{
+    ClientError, ClientResult, Event, EventAttendees, EventClient, EventRequestOptions,
+    EventResponseStatus,
+};
+
+/// AttendeeSummary counts the responses of the human attendees of an event.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttendeeSummary {
+    pub accepted: usize,
+    pub declined: usize,
+    pub tentative: usize,
+    pub pending: usize,
+}
+
+impl Event {
+    /// The attendee with the given email, compared case-insensitively.
+    pub fn attendee(&self, email: &str) -> Option<&EventAttendees> {
+        self.attendees
+            .iter()
+            .find(|a| a.email.eq_ignore_ascii_case(email))
+    }
+
+    /// The attendee entry of the authenticated user, if they are invited.
+    pub fn self_attendee(&self) -> Option<&EventAttendees> {
+        self.self_attendee_index().map(|idx| &self.attendees[idx])
+    }
+
+    fn self_attendee_index(&self) -> Option<usize> {
+        self.attendees
+            .iter()
+            .position(|a| a.appears_as_self == Some(true))
+    }
+
+    /// Whether the authenticated user is the organizer of the event.
+    pub fn is_organizer(&self) -> bool {
+        self.organizer.appears_as_self == Some(true)
+    }
+
+    /// Add an attendee, returning the existing entry when the email is already invited.
+    pub fn add_attendee(&mut self, email: impl ToString) -> &mut EventAttendees {
+        let email = email.to_string();
+        let idx = match self
+            .attendees
+            .iter()
+            .position(|a| a.email.eq_ignore_ascii_case(&email))
+        {
+            Some(idx) => idx,
+            None => {
+                self.attendees.push(EventAttendees {
+                    email,
+                    ..Default::default()
+                });
+                self.attendees.len() - 1
+            }
+        };
+        &mut self.attendees[idx]
+    }
+
+    /// Add an optional attendee.
+    pub fn add_optional_attendee(&mut self, email: impl ToString) -> &mut EventAttendees {
+        let attendee = self.add_attendee(email);
+        attendee.optional = Some(true);
+        attendee
+    }
+
+    /// Add a resource such as a meeting room, identified by its resource calendar email.
+    pub fn add_resource(&mut self, email: impl ToString) -> &mut EventAttendees {
+        let attendee = self.add_attendee(email);
+        attendee.resource = Some(true);
+        attendee
+    }
+
+    /// Remove an attendee or resource, returning its entry.
+    pub fn remove_attendee(&mut self, email: &str) -> Option<EventAttendees> {
+        let idx = self
+            .attendees
+            .iter()
+            .position(|a| a.email.eq_ignore_ascii_case(email))?;
+        Some(self.attendees.remove(idx))
+    }
+
+    /// Mark an attendee as optional or required. Returns false when the email is not invited.
+    pub fn set_optional(&mut self, email: &str, optional: bool) -> bool {
+        match self
+            .attendees
+            .iter_mut()
+            .find(|a| a.email.eq_ignore_ascii_case(email))
+        {
+            Some(attendee) => {
+                attendee.optional = Some(optional);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Count the responses of all attendees that are not resources.
+    pub fn attendee_summary(&self) -> AttendeeSummary {
+        let mut summary = AttendeeSummary::default();
+        for attendee in self.attendees.iter().filter(|a| a.resource != Some(true)) {
+            match attendee.response_status {
+                EventResponseStatus::Accepted => summary.accepted += 1,
+                EventResponseStatus::Declined => summary.declined += 1,
+                EventResponseStatus::Tentative => summary.tentative += 1,
+                _ => summary.pending += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl EventClient {
+    /// Save the attendee list of an event, e.g. after `Event::add_attendee` or
+    /// `Event::remove_attendee`. Only the attendees are sent, other fields are untouched.
+    pub async fn update_attendees(
+        &self,
+        event: &Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        self.patch_attendees(event, &event.attendees, options).await
+    }
+
+    /// Respond to an invitation as the authenticated user. Only the user's own entry is sent,
+    /// marked with `attendeesOmitted`, so Google changes nothing but its response status and
+    /// comment. The other attendees stay as they are, even when `event` is stale or lists only
+    /// some of them.
+    pub async fn respond(
+        &self,
+        event: &Event,
+        status: EventResponseStatus,
+        comment: Option<String>,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let mut attendee = event
+            .self_attendee()
+            .ok_or_else(|| ClientError::NotAnAttendee(event.id.clone()))?
+            .clone();
+        attendee.response_status = status;
+        if comment.is_some() {
+            attendee.comment = comment;
+        }
+
+        let mut fields = serde_json::Map::new();
+        fields.insert("attendees".to_string(), serde_json::to_value([attendee])?);
+        fields.insert("attendeesOmitted".to_string(), true.into());
+        self.patch_fields(event, fields, options).await
+    }
+
+    async fn patch_attendees(
+        &self,
+        event: &Event,
+        attendees: &[EventAttendees],
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let mut fields = serde_json::Map::new();
+        fields.insert("attendees".to_string(), serde_json::to_value(attendees)?);
+        self.patch_fields(event, fields, options).await
+    }
+}
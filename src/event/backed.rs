@@ -0,0 +1,112 @@
+use super::{
+    AdditionalProperties, ClientResult, Event, EventClient, EventListOptions, EventRequestOptions,
+};
+
+/// The private extended property holding the external ID of a `CalendarBacked` object.
+pub const EXTERNAL_ID_PROPERTY: &str = "externalId";
+
+/// CalendarBacked maps one of your own types to a Google Calendar event. The object is identified
+/// by a stable external ID stored in `extendedProperties.private`, which is what
+/// `EventClient::upsert` uses to find the event again.
+pub trait CalendarBacked: Sized {
+    /// A stable identifier of the object in your system, e.g. a ticket number.
+    fn external_id(&self) -> String;
+
+    /// Write the object onto the event. This is called for both new and existing events, fields
+    /// not touched here keep whatever value the event already has.
+    fn to_event(&self, event: &mut Event);
+
+    /// Rebuild the object from an event previously written by `to_event`.
+    fn from_event(event: &Event) -> ClientResult<Self>;
+
+    /// Additional private extended properties stored next to the external ID.
+    fn metadata(&self) -> AdditionalProperties {
+        AdditionalProperties::new()
+    }
+}
+
+impl Event {
+    /// The external ID of the `CalendarBacked` object this event was written from, if any.
+    pub fn external_id(&self) -> Option<&str> {
+        self.private_property(EXTERNAL_ID_PROPERTY)
+    }
+
+    /// Write a `CalendarBacked` object onto the event, including its external ID and metadata.
+    pub fn apply_backed(&mut self, item: &impl CalendarBacked) {
+        item.to_event(self);
+        for (key, value) in item.metadata() {
+            self.set_private_property(key, value);
+        }
+        self.set_private_property(EXTERNAL_ID_PROPERTY, item.external_id());
+    }
+}
+
+impl EventClient {
+    /// Find the event written for the given external ID.
+    pub async fn find_by_external_id(
+        &self,
+        calendar_id: String,
+        external_id: &str,
+    ) -> ClientResult<Option<Event>> {
+        let options =
+            EventListOptions::default().with_private_property(EXTERNAL_ID_PROPERTY, external_id);
+        Ok(self
+            .list(calendar_id, Some(options))
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Load the object stored under the given external ID.
+    pub async fn load<T: CalendarBacked>(
+        &self,
+        calendar_id: String,
+        external_id: &str,
+    ) -> ClientResult<Option<T>> {
+        self.find_by_external_id(calendar_id, external_id)
+            .await?
+            .map(|event| T::from_event(&event))
+            .transpose()
+    }
+
+    /// Create or update the event for a `CalendarBacked` object. The event is looked up by the
+    /// object's external ID, updated in place when found and inserted otherwise.
+    pub async fn upsert<T: CalendarBacked>(
+        &self,
+        calendar_id: String,
+        item: &T,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let existing = self
+            .find_by_external_id(calendar_id.clone(), &item.external_id())
+            .await?;
+
+        // A new event only sends what `to_event` set, like one from `EventBuilder`.
+        let mut event = existing.clone().unwrap_or_else(|| Event {
+            calendar_id: calendar_id.clone(),
+            built: true,
+            ..Event::new_event()
+        });
+        event.apply_backed(item);
+
+        let mut event = match existing {
+            Some(_) => self.update(event, options).await?,
+            None => self.insert(event, options).await?,
+        };
+        event.calendar_id = calendar_id;
+        Ok(event)
+    }
+
+    /// Delete the event written for the given external ID. Returns whether an event was found.
+    pub async fn delete_by_external_id(
+        &self,
+        calendar_id: String,
+        external_id: &str,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<bool> {
+        match self.find_by_external_id(calendar_id, external_id).await? {
+            Some(event) => self.delete(event, options).await.map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
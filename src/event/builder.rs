@@ -0,0 +1,305 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+
+use super::{
+    recurrence::resolve_local, ClientResult, DefaultReminder, Event, EventAttendees,
+    EventCalendarDate, EventReminder, EventStatus, EventTransparency, EventValidationError,
+    EventVisibility, ReminderMethod,
+};
+
+/// Google rejects more reminder overrides than this.
+const MAX_REMINDER_OVERRIDES: usize = 5;
+/// Google rejects reminders further ahead than four weeks.
+const MAX_REMINDER_MINUTES: u16 = 40320;
+
+/// EventBuilder creates a new event. Only what is set on the builder is sent to Google when the
+/// event is inserted, and the event is validated locally by `build`.
+///
+/// ```no_run
+/// # use gcal_rs::*;
+/// # fn example() -> ClientResult<()> {
+/// let start = chrono::Utc::now();
+/// let event = Event::builder("Planning")
+///     .calendar("primary")
+///     .time(&start, &(start + chrono::Duration::hours(1)))
+///     .attendee("sam@example.com")
+///     .reminder(ReminderMethod::PopUp, 10)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    event: Event,
+    time_zone: Option<String>,
+}
+
+impl EventBuilder {
+    pub fn new(summary: impl ToString) -> Self {
+        Self {
+            event: Event {
+                summary: summary.to_string(),
+                built: true,
+                ..Event::new_event()
+            },
+            time_zone: None,
+        }
+    }
+
+    /// The calendar the event is created on.
+    pub fn calendar(mut self, calendar_id: impl ToString) -> Self {
+        self.event.calendar_id = calendar_id.to_string();
+        self
+    }
+
+    pub fn start(mut self, start: EventCalendarDate) -> Self {
+        self.event.start = start;
+        self
+    }
+
+    pub fn end(mut self, end: EventCalendarDate) -> Self {
+        self.event.end = end;
+        self
+    }
+
+    /// A timed event from `start` to `end`.
+    pub fn time<Tz: TimeZone>(self, start: &DateTime<Tz>, end: &DateTime<Tz>) -> Self
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        self.start(EventCalendarDate::from_datetime(start))
+            .end(EventCalendarDate::from_datetime(end))
+    }
+
+    /// An all-day event from `first` to `last`, both included.
+    pub fn all_day(self, first: NaiveDate, last: NaiveDate) -> Self {
+        let end = last.succ_opt().unwrap_or(last);
+        self.start(EventCalendarDate::from_date(first))
+            .end(EventCalendarDate::from_date(end))
+    }
+
+    /// The IANA time zone of start and end, e.g. `Europe/Zurich`. Required for recurring events.
+    pub fn time_zone(mut self, time_zone: impl ToString) -> Self {
+        self.time_zone = Some(time_zone.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: impl ToString) -> Self {
+        self.event.description = Some(description.to_string());
+        self
+    }
+
+    pub fn location(mut self, location: impl ToString) -> Self {
+        self.event.location = Some(location.to_string());
+        self
+    }
+
+    pub fn color_id(mut self, color_id: impl ToString) -> Self {
+        self.event.color_id = Some(color_id.to_string());
+        self
+    }
+
+    pub fn attendee(mut self, email: impl ToString) -> Self {
+        self.event.add_attendee(email);
+        self
+    }
+
+    pub fn optional_attendee(mut self, email: impl ToString) -> Self {
+        self.event.add_optional_attendee(email);
+        self
+    }
+
+    /// Remind `minutes` before the start instead of using the calendar's default reminders.
+    pub fn reminder(mut self, method: ReminderMethod, minutes: u16) -> Self {
+        let reminders = &mut self.event.reminders;
+        reminders.use_default = false;
+        reminders
+            .overrides
+            .get_or_insert_with(Vec::new)
+            .push(DefaultReminder { method, minutes });
+        self
+    }
+
+    /// Neither the calendar's default reminders nor any others.
+    pub fn no_reminders(mut self) -> Self {
+        self.event.reminders.use_default = false;
+        self.event.reminders.overrides = Some(Vec::new());
+        self
+    }
+
+    /// Add an RRULE, EXDATE or RDATE line, e.g. a `RecurrenceRule`.
+    pub fn recurrence(mut self, line: impl ToString) -> Self {
+        self.event.recurrence.push(line.to_string());
+        self
+    }
+
+    pub fn status(mut self, status: EventStatus) -> Self {
+        self.event.status = status;
+        self
+    }
+
+    pub fn transparency(mut self, transparency: EventTransparency) -> Self {
+        self.event.transparency = transparency;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: EventVisibility) -> Self {
+        self.event.visibility = visibility;
+        self
+    }
+
+    pub fn guests_can_modify(mut self, allowed: bool) -> Self {
+        self.event.guests_can_modify = allowed;
+        self
+    }
+
+    pub fn guests_can_invite_others(mut self, allowed: bool) -> Self {
+        self.event.guests_invite_others = allowed;
+        self
+    }
+
+    pub fn guests_can_see_other_guests(mut self, allowed: bool) -> Self {
+        self.event.guests_can_see_other_guests = allowed;
+        self
+    }
+
+    pub fn private_property(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.event.set_private_property(key, value);
+        self
+    }
+
+    pub fn shared_property(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.event.set_shared_property(key, value);
+        self
+    }
+
+    /// Validate and return the event, ready for `EventClient::insert`.
+    pub fn build(mut self) -> ClientResult<Event> {
+        if let Some(tz) = self.time_zone {
+            self.event.start.time_zone = Some(tz.clone());
+            self.event.end.time_zone = Some(tz);
+        }
+        self.event.validate()?;
+        Ok(self.event)
+    }
+}
+
+impl Default for EventBuilder {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Event {
+    /// An event as Google creates it when nothing else is given: confirmed, with the calendar's
+    /// default reminders and guests allowed to invite others and see the guest list.
+    pub(crate) fn new_event() -> Self {
+        Self {
+            status: EventStatus::Confirmed,
+            reminders: EventReminder {
+                overrides: None,
+                use_default: true,
+            },
+            guests_invite_others: true,
+            guests_can_see_other_guests: true,
+            ..Default::default()
+        }
+    }
+
+    /// The body of an event from `EventBuilder`, without the fields a new event has anyway.
+    pub(crate) fn builder_body(&self) -> ClientResult<Vec<u8>> {
+        let defaults = serde_json::to_value(Self::new_event())?;
+        let mut body = serde_json::to_value(self)?;
+        if let serde_json::Value::Object(fields) = &mut body {
+            fields.retain(|key, value| defaults.get(key) != Some(value));
+        }
+        Ok(serde_json::to_vec(&body)?)
+    }
+
+    /// Start building a new event with the given title.
+    pub fn builder(summary: impl ToString) -> EventBuilder {
+        EventBuilder::new(summary)
+    }
+
+    /// Check locally what Google would reject or misapply: start and end must be set and of the
+    /// same kind with the end after the start, timed recurring events need a time zone, at most
+    /// 5 reminder overrides of up to four weeks and well-formed attendee emails.
+    pub fn validate(&self) -> Result<(), EventValidationError> {
+        if self.start.date.is_none() && self.start.date_time.is_none() {
+            return Err(EventValidationError::MissingStart);
+        }
+        if self.end.date.is_none() && self.end.date_time.is_none() {
+            return Err(EventValidationError::MissingEnd);
+        }
+        if self.start.date.is_some() != self.end.date.is_some() {
+            return Err(EventValidationError::MixedAllDay);
+        }
+        let timed = self.start.date.is_none();
+        if !self.recurrence.is_empty()
+            && timed
+            && (self.start.time_zone.is_none() || self.end.time_zone.is_none())
+        {
+            return Err(EventValidationError::MissingTimeZone);
+        }
+        if instant(&self.end)? <= instant(&self.start)? {
+            return Err(EventValidationError::EndNotAfterStart {
+                start: value(&self.start).to_string(),
+                end: value(&self.end).to_string(),
+            });
+        }
+
+        let overrides = self.reminders.overrides.as_deref().unwrap_or_default();
+        if overrides.len() > MAX_REMINDER_OVERRIDES {
+            return Err(EventValidationError::TooManyReminders(overrides.len()));
+        }
+        if let Some(reminder) = overrides.iter().find(|r| r.minutes > MAX_REMINDER_MINUTES) {
+            return Err(EventValidationError::ReminderOutOfRange(reminder.minutes));
+        }
+
+        match self.attendees.iter().find(|a| !is_valid_email(&a.email)) {
+            Some(EventAttendees { email, .. }) => {
+                Err(EventValidationError::InvalidEmail(email.clone()))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// The date or date-time of an event date as written.
+fn value(date: &EventCalendarDate) -> &str {
+    date.date_time
+        .as_deref()
+        .or(date.date.as_deref())
+        .unwrap_or_default()
+}
+
+/// The UTC instant of an event date. Date-times without an offset are read in the date's time
+/// zone, all-day dates start at midnight UTC, which is enough to order dates of the same kind.
+fn instant(date: &EventCalendarDate) -> Result<NaiveDateTime, EventValidationError> {
+    let invalid = || EventValidationError::InvalidDate(value(date).to_string());
+    if let Some(dt) = &date.date_time {
+        if let Ok(dt) = DateTime::parse_from_rfc3339(dt) {
+            return Ok(dt.naive_utc());
+        }
+        let local =
+            NaiveDateTime::parse_from_str(dt, "%Y-%m-%dT%H:%M:%S").map_err(|_| invalid())?;
+        let tz: chrono_tz::Tz = match &date.time_zone {
+            Some(tz) => tz.parse().map_err(|_| invalid())?,
+            None => return Err(invalid()),
+        };
+        return Ok(resolve_local(&tz, local).naive_utc());
+    }
+    let day = NaiveDate::parse_from_str(value(date), "%Y-%m-%d").map_err(|_| invalid())?;
+    Ok(day.and_time(chrono::NaiveTime::MIN))
+}
+
+/// A plausibility check, not RFC 5322: one `@` with a local part and a dotted domain.
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && domain.split('.').count() > 1
+        && domain.split('.').all(|label| !label.is_empty())
+}
@@ -1,6 +1,12 @@
 use std::{sync::Arc, default::Default};
 
-use super::{ClientResult, Event, Events, GCalClient, SendUpdates, EventListOptions};
+use futures_util::{stream, Stream, TryStreamExt};
+
+use super::{
+    options_query, resolve_calendar_date, ClientError, ClientResult, Event, EventCalendarDate,
+    EventConferenceEntryPoint, EventConferenceStatusCode, EventInstancesOptions, EventListOptions,
+    EventPatch, EventRequestOptions, Events, GCalClient, EXTERNAL_ID_PROPERTY,
+};
 
 /// EventClient is the method of managing events from a specific calendar. Requires a Google
 /// Calendar client.
@@ -13,8 +19,35 @@ impl EventClient {
         Self(client)
     }
 
+    /// PATCH only the given fields of an event.
+    pub(crate) async fn patch_fields(
+        &self,
+        event: &Event,
+        fields: serde_json::Map<String, serde_json::Value>,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let query_string = match options {
+            Some(options) => options_query(&options)?,
+            None => Vec::new(),
+        };
+        let patch = EventPatch {
+            fields,
+            event,
+            query_string,
+        };
+
+        let mut updated: Event = self.0.patch(None, patch).await?.json().await?;
+        updated.calendar_id = event.calendar_id.clone();
+        Ok(updated)
+    }
+
     /// Delete the event.
-    pub async fn delete(&self, event: Event) -> ClientResult<()> {
+    pub async fn delete(
+        &self,
+        mut event: Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<()> {
+        EventRequestOptions::apply(options, &mut event)?;
         self.0.delete(None, event).await?;
         Ok(())
     }
@@ -31,7 +64,12 @@ impl EventClient {
 
     /// Import an event. See the Google Calendar documentation for the differences between import
     /// and insert.
-    pub async fn import(&self, event: Event) -> ClientResult<Event> {
+    pub async fn import(
+        &self,
+        mut event: Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        EventRequestOptions::apply(options, &mut event)?;
         Ok(self
             .0
             .post(Some("import".to_string()), event)
@@ -42,10 +80,15 @@ impl EventClient {
 
     /// Insert an event. See the Google Calendar documentation for the differences between import
     /// and insert.
-    pub async fn insert(&self, mut event: Event) -> ClientResult<Event> {
+    pub async fn insert(
+        &self,
+        mut event: Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
         if !event.attachments.is_empty() {
             event.add_query("supportsAttachments".to_string(), "true".to_string());
         }
+        EventRequestOptions::apply(options, &mut event)?;
         Ok(self
             .0
             .post(Some(String::new()), event)
@@ -54,14 +97,60 @@ impl EventClient {
             .await?)
     }
 
-    /// Retrieve all instances for a recurring event.
-    pub async fn instances(&self, event: Event) -> ClientResult<Events> {
-        Ok(self
+    /// Retrieve a page of instances for a recurring event.
+    pub async fn instances(
+        &self,
+        event: &Event,
+        options: Option<EventInstancesOptions>,
+    ) -> ClientResult<Events> {
+        let mut series = Event {
+            id: event.id.clone(),
+            calendar_id: event.calendar_id.clone(),
+            ..Default::default()
+        };
+        if let Some(opts) = options {
+            for (key, value) in options_query(&opts)? {
+                series.append_query(key, value);
+            }
+        }
+
+        let mut events: Events = self
             .0
-            .get(Some("instances".to_string()), event)
+            .get(Some("instances".to_string()), series)
             .await?
             .json()
-            .await?)
+            .await?;
+        events.add_calendar(event.calendar_id.clone());
+        Ok(events)
+    }
+
+    /// Retrieve all instances for a recurring event, following every page.
+    pub async fn instances_all(
+        &self,
+        event: &Event,
+        options: Option<EventInstancesOptions>,
+    ) -> ClientResult<Vec<Event>> {
+        self.instances_stream(event, options).try_collect().await
+    }
+
+    /// Stream the instances for a recurring event, fetching further pages as they are needed.
+    pub fn instances_stream<'a>(
+        &'a self,
+        event: &'a Event,
+        options: Option<EventInstancesOptions>,
+    ) -> impl Stream<Item = ClientResult<Event>> + 'a {
+        stream::try_unfold(Some(options.unwrap_or_default()), move |options| async move {
+            let Some(options) = options else {
+                return Ok::<_, ClientError>(None);
+            };
+            let page = self.instances(event, Some(options.clone())).await?;
+            let next = page.next_page_token.map(|token| EventInstancesOptions {
+                page_token: Some(token),
+                ..options
+            });
+            Ok(Some((stream::iter(page.items.into_iter().map(Ok)), next)))
+        })
+        .try_flatten()
     }
 
     /// List events between the start and end times.
@@ -77,20 +166,12 @@ impl EventClient {
     
         if let Some(opts) = options {
             // Convert the options to query parameters automatically
-            let query_params = serde_qs::to_string(&opts)?;
-            for (key, value) in serde_qs::from_str::<Vec<(String, String)>>(&query_params)? {
-                event.add_query(key, value);
+            for (key, value) in options_query(&opts)? {
+                event.append_query(key, value);
             }
         }
     
-        let mut events = self
-            .0
-            .get(None, event)
-            .await
-            .expect("Fail here 1")
-            .json::<Events>()
-            .await
-            .expect("Fail 2");
+        let mut events = self.0.get(None, event).await?.json::<Events>().await?;
         events.add_calendar(calendar_id);
         Ok(events.items)
     }
@@ -100,33 +181,280 @@ impl EventClient {
         &self,
         mut event: Event,
         destination: String,
-        send_updates: Option<SendUpdates>,
+        options: Option<EventRequestOptions>,
     ) -> ClientResult<()> {
         event.add_query("destination".to_string(), destination);
-        event.add_query(
-            "sendUpdates".to_string(),
-            send_updates.map_or_else(|| "false".to_string(), |x| x.to_string()),
-        );
+        EventRequestOptions::apply(options, &mut event)?;
 
         self.0.post(Some("move".to_string()), event).await?;
         Ok(())
     }
 
-    /// Add an event with the summary.
-    pub async fn add(&self, text: String) -> ClientResult<Event> {
-        let mut event = Event::default();
+    /// Create an event from a line of text such as `Lunch with Sam tomorrow at noon`, parsed by
+    /// Google (quickAdd). Use `QuickAddPreview` to see what the text will likely create first.
+    pub async fn add(
+        &self,
+        calendar_id: String,
+        text: String,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let mut event = Event {
+            calendar_id: calendar_id.clone(),
+            ..Default::default()
+        };
         event.add_query("text".to_string(), text);
+        EventRequestOptions::apply(options, &mut event)?;
 
-        Ok(self
+        let mut created: Event = self
             .0
             .post(Some("quickAdd".to_string()), event)
             .await?
             .json()
-            .await?)
+            .await?;
+        created.calendar_id = calendar_id;
+        Ok(created)
     }
 
-    /// Update an event.
-    pub async fn update(&self, event: Event) -> ClientResult<Event> {
+    /// Update an event. Attachments are only sent when the event has some; set
+    /// `supports_attachments` in the options or use `update_attachments` to remove the last one.
+    pub async fn update(
+        &self,
+        mut event: Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        if !event.attachments.is_empty() {
+            event.add_query("supportsAttachments".to_string(), "true".to_string());
+        }
+        EventRequestOptions::apply(options, &mut event)?;
         Ok(self.0.put(None, event).await?.json().await?)
     }
+
+    /// Patch an event. Only the fields that are serialized are changed, so an empty attachment
+    /// list is left alone; use `update_attachments` to remove the last attachment.
+    pub async fn patch(
+        &self,
+        mut event: Event,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        if !event.attachments.is_empty() {
+            event.add_query("supportsAttachments".to_string(), "true".to_string());
+        }
+        EventRequestOptions::apply(options, &mut event)?;
+        Ok(self.0.patch(None, event).await?.json().await?)
+    }
+
+    /// Wait for a conference requested with `Event::request_conference` to be created, checking
+    /// every `interval` up to `attempts` times. Returns the conference entry points once the
+    /// request succeeded.
+    pub async fn await_conference(
+        &self,
+        event: &Event,
+        interval: std::time::Duration,
+        attempts: u32,
+    ) -> ClientResult<Vec<EventConferenceEntryPoint>> {
+        let mut current = event.clone();
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                tokio::time::sleep(interval).await;
+                current = self
+                    .get(event.calendar_id.clone(), event.id.clone())
+                    .await?;
+            }
+            let Some(data) = &current.conference_data else {
+                continue;
+            };
+            match data.status() {
+                Some(EventConferenceStatusCode::Pending) => continue,
+                Some(EventConferenceStatusCode::Failure) => {
+                    return Err(ClientError::ConferenceFailed(format!(
+                        "creation failed for event `{}`",
+                        event.id
+                    )))
+                }
+                _ if data.entry_points.is_empty() => continue,
+                _ => return Ok(data.entry_points.clone()),
+            }
+        }
+        Err(ClientError::ConferenceFailed(format!(
+            "still pending for event `{}` after {} attempts",
+            event.id, attempts
+        )))
+    }
+
+    /// Find the occurrence of a recurring event that originally started at `original_start`.
+    /// `event` may be the series itself or any of its instances.
+    pub async fn instance(
+        &self,
+        event: &Event,
+        original_start: &EventCalendarDate,
+    ) -> ClientResult<Event> {
+        let id = series_id(event);
+        let start = original_start
+            .date_time
+            .clone()
+            .or_else(|| original_start.date.clone())
+            .unwrap_or_default();
+        let series = Event {
+            id: id.clone(),
+            calendar_id: event.calendar_id.clone(),
+            ..Default::default()
+        };
+        let options = EventInstancesOptions {
+            original_start: Some(start.clone()),
+            ..Default::default()
+        };
+
+        let events = self.instances(&series, Some(options)).await?;
+        events.items.into_iter().next().ok_or_else(|| {
+            ClientError::InvalidRecurrence(format!("no instance of `{}` starts at {}", id, start))
+        })
+    }
+
+    /// Fetch the recurring series an event belongs to. Returns the event itself when it is not
+    /// an instance.
+    pub async fn series(&self, event: &Event) -> ClientResult<Event> {
+        if event.recurring_event_id.is_empty() {
+            return Ok(event.clone());
+        }
+        let mut series = self
+            .get(event.calendar_id.clone(), event.recurring_event_id.clone())
+            .await?;
+        series.calendar_id = event.calendar_id.clone();
+        Ok(series)
+    }
+
+    /// Modify a single occurrence of a recurring event ("this event"). The instance keeps its
+    /// ID, `recurring_event_id` and `original_start_time` so it stays attached to the series.
+    pub async fn update_instance(
+        &self,
+        event: &Event,
+        original_start: &EventCalendarDate,
+        edit: impl FnOnce(&mut Event),
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let mut instance = self.instance(event, original_start).await?;
+        let id = instance.id.clone();
+        let recurring_event_id = instance.recurring_event_id.clone();
+        let original_start_time = instance.original_start_time.clone();
+
+        edit(&mut instance);
+        instance.id = id;
+        instance.recurring_event_id = recurring_event_id;
+        instance.original_start_time = original_start_time;
+        instance.recurrence.clear();
+
+        let mut updated = self.update(instance, options).await?;
+        updated.calendar_id = event.calendar_id.clone();
+        Ok(updated)
+    }
+
+    /// Cancel a single occurrence of a recurring event. The rest of the series is untouched.
+    pub async fn cancel_instance(
+        &self,
+        event: &Event,
+        original_start: &EventCalendarDate,
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<()> {
+        self.delete(self.instance(event, original_start).await?, options)
+            .await
+    }
+
+    /// Modify an occurrence and every one after it ("this and following events"). The series is
+    /// ended right before `original_start` with UNTIL and a new series starting there is created
+    /// with `edit` applied. Returns the truncated series, or `None` when `original_start` is the
+    /// first occurrence and the whole series was edited instead, and the following series.
+    pub async fn update_following(
+        &self,
+        event: &Event,
+        original_start: &EventCalendarDate,
+        edit: impl FnOnce(&mut Event),
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<(Option<Event>, Event)> {
+        let mut series = self.series(event).await?;
+        let tz = series.time_zone()?;
+        let (start, all_day) = resolve_calendar_date(&series.start, &tz)?;
+        let (end, _) = resolve_calendar_date(&series.end, &tz)?;
+        let (split, _) = resolve_calendar_date(original_start, &tz)?;
+
+        if split <= start {
+            return Ok((None, self.update_series(&series, edit, options).await?));
+        }
+
+        let (previous, following) = series.split_recurrence(original_start)?;
+        let date = |dt: chrono::DateTime<chrono_tz::Tz>| {
+            if all_day {
+                EventCalendarDate {
+                    date: Some(dt.format("%Y-%m-%d").to_string()),
+                    ..Default::default()
+                }
+            } else {
+                EventCalendarDate {
+                    date_time: Some(dt.to_rfc3339()),
+                    time_zone: series.start.time_zone.clone(),
+                    ..Default::default()
+                }
+            }
+        };
+        let mut next = Event {
+            start: date(split),
+            end: date(split + (end - start)),
+            recurrence: following,
+            id: String::new(),
+            etag: String::new(),
+            html_link: String::new(),
+            ical_uid: None,
+            recurring_event_id: String::new(),
+            original_start_time: Default::default(),
+            sequence: 0,
+            ..series.clone()
+        };
+        // The following series is a new event: it gets its own conference, and neither the
+        // CalendarBacked external ID nor the guests' responses carry over.
+        next.conference_data = None;
+        next.remove_private_property(EXTERNAL_ID_PROPERTY);
+        for attendee in &mut next.attendees {
+            attendee.response_status = Default::default();
+        }
+        edit(&mut next);
+
+        // Create the following series before truncating the old one, so a failure never loses
+        // the following occurrences. If the truncation fails, the new series is removed again.
+        let mut next = self.insert(next, options.clone()).await?;
+        next.calendar_id = event.calendar_id.clone();
+        series.recurrence = previous;
+        let mut previous = match self.update(series, options.clone()).await {
+            Ok(previous) => previous,
+            Err(e) => {
+                let _ = self.delete(next, options).await;
+                return Err(e);
+            }
+        };
+        previous.calendar_id = event.calendar_id.clone();
+        Ok((Some(previous), next))
+    }
+
+    /// Modify every occurrence of a recurring event ("all events"). `event` may be the series
+    /// itself or any of its instances.
+    pub async fn update_series(
+        &self,
+        event: &Event,
+        edit: impl FnOnce(&mut Event),
+        options: Option<EventRequestOptions>,
+    ) -> ClientResult<Event> {
+        let mut series = self.series(event).await?;
+        edit(&mut series);
+
+        let mut updated = self.update(series, options).await?;
+        updated.calendar_id = event.calendar_id.clone();
+        Ok(updated)
+    }
+}
+
+/// The ID of the recurring series an event belongs to.
+fn series_id(event: &Event) -> String {
+    if event.recurring_event_id.is_empty() {
+        event.id.clone()
+    } else {
+        event.recurring_event_id.clone()
+    }
 }
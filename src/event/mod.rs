@@ -1,11 +1,32 @@
-use std::collections::BTreeSet;
-
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
 mod client;
 pub use client::EventClient;
 
+/// Attendee management and responding to invitations.
+pub mod attendees;
+pub use attendees::*;
+
+/// Google Drive attachments.
+mod attachments;
+
+/// Building new events with local validation.
+pub mod builder;
+pub use builder::*;
+
+/// Mapping your own types onto events through extended properties.
+pub mod backed;
+pub use backed::*;
+
+/// Offline preview of what quickAdd will create from a piece of text.
+pub mod quick_add;
+pub use quick_add::*;
+
+/// Recurrence rules, RRULE/EXDATE/RDATE parsing and local expansion of occurrences.
+pub mod recurrence;
+pub use recurrence::*;
+
 pub mod types;
 use types::*;
 
@@ -46,8 +67,11 @@ pub struct Event {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub attendees: Vec<EventAttendees>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub birthday_properties: Option<EventBirthdayProperties>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color_id: Option<String>,
-    pub conference_data: EventConferenceData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conference_data: Option<EventConferenceData>,
     pub created: String,
     pub creator: EventCreator,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -56,6 +80,10 @@ pub struct Event {
     pub end_time_unspecified: bool,
     pub etag: String,
     pub event_type: EventType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended_properties: Option<EventExtendedProperties>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_time_properties: Option<EventFocusTimeProperties>,
     pub gadget: EventGadget,
     #[serde(default = "default_true")]
     pub guests_invite_others: bool,
@@ -64,6 +92,7 @@ pub struct Event {
     pub guests_can_modify: bool,
     pub hangout_link: String,
     pub html_link: String,
+    #[serde(rename = "iCalUID", skip_serializing_if = "Option::is_none")]
     pub ical_uid: Option<String>,
     pub id: String,
     #[serde(default = "default_event_kind")]
@@ -73,10 +102,12 @@ pub struct Event {
     pub locked: bool,
     pub organizer: EventOrganizer,
     pub original_start_time: EventCalendarDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_of_office_properties: Option<EventOutOfOfficeProperties>,
     pub private_copy: bool,
     pub recurring_event_id: String,
-    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
-    pub recurrence: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recurrence: Vec<String>,
     pub reminders: EventReminder,
     pub sequence: u64,
     pub source: EventSource,
@@ -86,12 +117,16 @@ pub struct Event {
     pub transparency: EventTransparency,
     pub updated: String,
     pub visibility: EventVisibility,
-    pub working_location: EventWorkingLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_location_properties: Option<EventWorkingLocation>,
 
     #[serde(skip)]
     pub calendar_id: String,
     #[serde(skip)]
     query_string: QueryParams,
+    /// Built by `EventBuilder`, only fields that differ from a new event are sent.
+    #[serde(skip)]
+    built: bool,
 }
 
 impl Sendable for Event {
@@ -107,6 +142,52 @@ impl Sendable for Event {
     fn query(&self) -> QueryParams {
         self.query_string.clone()
     }
+
+    fn body_bytes(&self) -> ClientResult<Vec<u8>> {
+        if self.built {
+            return self.builder_body();
+        }
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    fn scopes(&self, write: bool) -> &'static [CalendarScope] {
+        if write {
+            scope::EVENTS_WRITE
+        } else {
+            scope::EVENTS_READ
+        }
+    }
+}
+
+/// A PATCH body that only carries some fields of an event, e.g. its attendees. Unlike `Event`,
+/// empty lists are sent so that they can be cleared.
+#[derive(Serialize)]
+pub(crate) struct EventPatch<'a> {
+    #[serde(flatten)]
+    pub(crate) fields: serde_json::Map<String, serde_json::Value>,
+
+    #[serde(skip)]
+    pub(crate) event: &'a Event,
+    #[serde(skip)]
+    pub(crate) query_string: QueryParams,
+}
+
+impl Sendable for EventPatch<'_> {
+    fn path(&self, action: Option<String>) -> String {
+        self.event.path(action)
+    }
+
+    fn query(&self) -> QueryParams {
+        self.query_string.clone()
+    }
+
+    fn scopes(&self, write: bool) -> &'static [CalendarScope] {
+        if write {
+            scope::EVENTS_WRITE
+        } else {
+            scope::EVENTS_READ
+        }
+    }
 }
 
 impl Events {
@@ -118,11 +199,312 @@ impl Events {
 }
 
 impl Event {
+    /// An out-of-office block. Conflicting invitations are declined with `decline_message`
+    /// according to `mode`.
+    pub fn out_of_office(
+        start: EventCalendarDate,
+        end: EventCalendarDate,
+        mode: EventAutoDeclineMode,
+        decline_message: Option<String>,
+    ) -> Self {
+        Self {
+            summary: "Out of office".to_string(),
+            start,
+            end,
+            event_type: EventType::OutOfOffice,
+            out_of_office_properties: Some(EventOutOfOfficeProperties {
+                auto_decline_mode: Some(mode),
+                decline_message,
+            }),
+            built: true,
+            ..Event::new_event()
+        }
+    }
+
+    /// A focus-time block, setting the chat status for its duration. Conflicting invitations
+    /// are declined according to `mode`.
+    pub fn focus_time(
+        start: EventCalendarDate,
+        end: EventCalendarDate,
+        mode: EventAutoDeclineMode,
+        chat_status: EventChatStatus,
+    ) -> Self {
+        Self {
+            summary: "Focus time".to_string(),
+            start,
+            end,
+            event_type: EventType::FocusTime,
+            focus_time_properties: Some(EventFocusTimeProperties {
+                auto_decline_mode: Some(mode),
+                decline_message: None,
+                chat_status: Some(chat_status),
+            }),
+            built: true,
+            ..Event::new_event()
+        }
+    }
+
+    /// A working-location event, usually an all-day event with transparency `Transparent`.
+    pub fn working_location(
+        start: EventCalendarDate,
+        end: EventCalendarDate,
+        location: EventWorkingLocation,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            event_type: EventType::WorkingLocation,
+            transparency: EventTransparency::Transparent,
+            visibility: EventVisibility::Public,
+            working_location_properties: Some(location),
+            built: true,
+            ..Event::new_event()
+        }
+    }
+
     pub fn add_query(&mut self, key: String, value: String) {
-        self.query_string.insert(key, value);
+        set_query(&mut self.query_string, key, value);
+    }
+
+    /// Add a query parameter without replacing previous values of the key.
+    pub fn append_query(&mut self, key: String, value: String) {
+        self.query_string.push((key, value));
+    }
+
+    /// Ask Google to create a new conference, e.g. a Google Meet link, when the event is
+    /// inserted or updated.
+    pub fn request_conference(&mut self, typ: EventConferenceSolutionKeyType) {
+        self.conference_data = Some(EventConferenceData::create(typ));
+        self.set_conference_data_version(1);
+    }
+
+    /// Set the `conferenceDataVersion` the request is sent with. Version 0 ignores any changes
+    /// to `conference_data`, version 1 applies them.
+    pub fn set_conference_data_version(&mut self, version: u8) {
+        self.add_query("conferenceDataVersion".to_string(), version.to_string());
+    }
+
+    /// A private extended property, only visible on this calendar's copy of the event.
+    pub fn private_property(&self, key: &str) -> Option<&str> {
+        self.extended_properties
+            .as_ref()
+            .and_then(|p| p.private.as_ref())
+            .and_then(|p| p.get(key))
+            .map(String::as_str)
+    }
+
+    /// A shared extended property, visible on every attendee's copy of the event.
+    pub fn shared_property(&self, key: &str) -> Option<&str> {
+        self.extended_properties
+            .as_ref()
+            .and_then(|p| p.shared.as_ref())
+            .and_then(|p| p.get(key))
+            .map(String::as_str)
+    }
+
+    /// Set a private extended property.
+    pub fn set_private_property(&mut self, key: impl ToString, value: impl ToString) {
+        self.extended_properties
+            .get_or_insert_with(Default::default)
+            .private
+            .get_or_insert_with(Default::default)
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Set a shared extended property.
+    pub fn set_shared_property(&mut self, key: impl ToString, value: impl ToString) {
+        self.extended_properties
+            .get_or_insert_with(Default::default)
+            .shared
+            .get_or_insert_with(Default::default)
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Remove a private extended property, returning its value.
+    pub fn remove_private_property(&mut self, key: &str) -> Option<String> {
+        self.extended_properties
+            .as_mut()
+            .and_then(|p| p.private.as_mut())
+            .and_then(|p| p.remove(key))
+    }
+
+    /// Remove a shared extended property, returning its value.
+    pub fn remove_shared_property(&mut self, key: &str) -> Option<String> {
+        self.extended_properties
+            .as_mut()
+            .and_then(|p| p.shared.as_mut())
+            .and_then(|p| p.remove(key))
+    }
+
+    /// Parse the `recurrence` lines of this event.
+    pub fn parse_recurrence(&self) -> ClientResult<Recurrence> {
+        Recurrence::parse(self.recurrence.iter().map(String::as_str))
+    }
+
+    /// The RRULE lines of this event, in order.
+    pub fn rrules(&self) -> ClientResult<Vec<RecurrenceRule>> {
+        Ok(self.parse_recurrence()?.rules)
+    }
+
+    /// The dates excluded from the series by EXDATE lines, in order.
+    pub fn exdates(&self) -> ClientResult<Vec<RecurrenceDate>> {
+        Ok(self.parse_recurrence()?.exdates)
+    }
+
+    /// The dates added to the series by RDATE lines, in order.
+    pub fn rdates(&self) -> ClientResult<Vec<RecurrenceDate>> {
+        Ok(self.parse_recurrence()?.rdates)
+    }
+
+    /// Append an RRULE line.
+    pub fn add_rrule(&mut self, rule: RecurrenceRule) {
+        self.recurrence.push(rule.to_string());
+    }
+
+    /// Append an EXDATE line excluding a single occurrence.
+    pub fn add_exdate(&mut self, date: RecurrenceDate) {
+        self.recurrence.push(date.to_line("EXDATE"));
+    }
+
+    /// Append an RDATE line adding a single occurrence.
+    pub fn add_rdate(&mut self, date: RecurrenceDate) {
+        self.recurrence.push(date.to_line("RDATE"));
+    }
+
+    /// Expand the event into the occurrences overlapping `[from, to)` without a round-trip to
+    /// `EventClient::instances`. Occurrences are computed in the event's start time zone, falling
+    /// back to UTC when none is set. Non-recurring events yield at most themselves.
+    pub fn occurrences<Z: chrono::TimeZone>(
+        &self,
+        from: &chrono::DateTime<Z>,
+        to: &chrono::DateTime<Z>,
+    ) -> ClientResult<Vec<Occurrence>> {
+        let tz = self.time_zone()?;
+        let (start, all_day) = resolve_calendar_date(&self.start, &tz)?;
+        let (end, _) = resolve_calendar_date(&self.end, &tz)?;
+        let duration = end - start;
+        let days = (end.date_naive() - start.date_naive()).num_days();
+
+        let from = from.with_timezone(&tz);
+        let to = to.with_timezone(&tz);
+        Ok(self
+            .parse_recurrence()?
+            .between(&start, &(from - duration), &to)
+            .into_iter()
+            .map(|start| Occurrence {
+                start,
+                // All-day events last whole days, which are not always 24 hours long.
+                end: if all_day {
+                    recurrence::resolve_local(
+                        &tz,
+                        (start.date_naive() + chrono::Duration::days(days))
+                            .and_time(chrono::NaiveTime::MIN),
+                    )
+                } else {
+                    start + duration
+                },
+                all_day,
+            })
+            .filter(|o| o.end > from || (duration.is_zero() && o.start >= from))
+            .collect())
+    }
+
+    /// Split the recurrence lines at the occurrence starting at `at`. The first set ends the
+    /// series right before `at` using UNTIL, the second continues it from `at` and carries over
+    /// the remaining COUNT. EXDATE and RDATE values go to the set whose occurrences they fall
+    /// in. Rules exhausted before `at` are dropped from the second set, and splitting where no
+    /// occurrence remains is an error.
+    pub fn split_recurrence(
+        &self,
+        at: &EventCalendarDate,
+    ) -> ClientResult<(Vec<String>, Vec<String>)> {
+        let tz = self.time_zone()?;
+        let (start, all_day) = resolve_calendar_date(&self.start, &tz)?;
+        let (split, _) = resolve_calendar_date(at, &tz)?;
+        let until = if all_day {
+            RecurrenceDate::Date(split.date_naive() - chrono::Duration::days(1))
+        } else {
+            RecurrenceDate::Utc((split - chrono::Duration::seconds(1)).to_utc())
+        };
+        let time = start.naive_local().time();
+        let is_following = |d: &RecurrenceDate| d.resolve(&tz, time) >= split;
+
+        let mut previous = Vec::new();
+        let mut following = Vec::new();
+        let mut remaining = false;
+        for line in &self.recurrence {
+            if !line.starts_with("RRULE") {
+                let dates = Recurrence::parse([line.as_str()])?;
+                for (name, dates) in [("EXDATE", dates.exdates), ("RDATE", dates.rdates)] {
+                    for date in dates {
+                        if is_following(&date) {
+                            remaining |= name == "RDATE";
+                            following.push(date.to_line(name));
+                        } else {
+                            previous.push(date.to_line(name));
+                        }
+                    }
+                }
+                continue;
+            }
+            let rule: RecurrenceRule = line.parse()?;
+            let next = match (rule.count, &rule.until) {
+                (Some(count), _) => {
+                    let before = rule.occurrences(&start, &split).len() as u32;
+                    (count > before).then(|| rule.clone().with_count(count - before))
+                }
+                (_, Some(until)) if !is_following(until) => None,
+                _ => Some(Ok(rule.clone())),
+            };
+            // A rule that already ends before the split is kept as it is.
+            let Some(next) = next else {
+                previous.push(line.clone());
+                continue;
+            };
+            following.push(next?.to_string());
+            remaining = true;
+            previous.push(rule.with_until(until).to_string());
+        }
+        if !remaining {
+            return Err(ClientError::InvalidRecurrence(format!(
+                "no occurrence of `{}` remains at {}",
+                self.id, split
+            )));
+        }
+        Ok((previous, following))
+    }
+
+    /// The time zone of the event's start, falling back to UTC when none is set.
+    pub(crate) fn time_zone(&self) -> ClientResult<chrono_tz::Tz> {
+        match &self.start.time_zone {
+            Some(tz) => tz
+                .parse()
+                .map_err(|_| ClientError::InvalidRecurrence(format!("unknown time zone `{}`", tz))),
+            None => Ok(chrono_tz::Tz::UTC),
+        }
     }
 }
 
+/// Resolve an event date in the given time zone, returning whether it is an all-day date.
+pub(crate) fn resolve_calendar_date(
+    date: &EventCalendarDate,
+    tz: &chrono_tz::Tz,
+) -> ClientResult<(chrono::DateTime<chrono_tz::Tz>, bool)> {
+    let invalid = |v: &str| ClientError::InvalidRecurrence(format!("invalid event date `{}`", v));
+    if let Some(dt) = &date.date_time {
+        let dt = chrono::DateTime::parse_from_rfc3339(dt).map_err(|_| invalid(dt))?;
+        return Ok((dt.with_timezone(tz), false));
+    }
+    if let Some(d) = &date.date {
+        let d = chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| invalid(d))?;
+        return Ok((
+            recurrence::resolve_local(tz, d.and_time(chrono::NaiveTime::MIN)),
+            true,
+        ));
+    }
+    Err(invalid(""))
+}
+
 fn default_event_kind() -> String {
     "calendar#event".to_string()
 }
@@ -164,8 +546,9 @@ pub struct EventListOptions {
     pub max_results: Option<i32>,
     pub order_by: Option<String>,
     pub page_token: Option<String>,
+    pub private_extended_property: Option<Vec<String>>,
     pub q: Option<String>,
-    pub shared_extended_property: Option<String>,
+    pub shared_extended_property: Option<Vec<String>>,
     pub show_deleted: Option<bool>,
     pub show_hidden_invitations: Option<bool>,
     pub single_events: Option<bool>,
@@ -175,23 +558,81 @@ pub struct EventListOptions {
     pub timezone: Option<String>,
     pub updated_min: Option<chrono::DateTime<chrono::Local>>,
 }
-#[derive(Debug, Clone, Serialize)]
+
+impl EventListOptions {
+    /// Only return events with a private extended property `key` set to `value`. Multiple
+    /// filters must all match.
+    pub fn with_private_property(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.private_extended_property
+            .get_or_insert_with(Vec::new)
+            .push(format!("{}={}", key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Only return events with a shared extended property `key` set to `value`. Multiple
+    /// filters must all match.
+    pub fn with_shared_property(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.shared_extended_property
+            .get_or_insert_with(Vec::new)
+            .push(format!("{}={}", key.to_string(), value.to_string()));
+        self
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Default, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub enum EventTypes {
-    Default,
-    FocusTime,
-    OutOfOffice,
-    WorkingLocation,
-}
-
-impl std::fmt::Display for EventTypes {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let s = match self {
-            EventTypes::Default => "default",
-            EventTypes::FocusTime => "focusTime",
-            EventTypes::OutOfOffice => "outOfOffice",
-            EventTypes::WorkingLocation => "workingLocation",
-        };
-        write!(f, "{}", s)
+pub struct EventInstancesOptions {
+    pub max_attendees: Option<i32>,
+    pub max_results: Option<i32>,
+    pub original_start: Option<String>,
+    pub page_token: Option<String>,
+    pub show_deleted: Option<bool>,
+    pub time_max: Option<chrono::DateTime<chrono::Local>>,
+    pub time_min: Option<chrono::DateTime<chrono::Local>>,
+    pub time_zone: Option<String>,
+}
+
+/// Options accepted by every request that creates, changes or deletes an event.
+#[skip_serializing_none]
+#[derive(Default, Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventRequestOptions {
+    /// Which guests are notified about the change. Google notifies nobody when unset.
+    pub send_updates: Option<SendUpdates>,
+    /// 0 ignores changes to `conference_data`, 1 applies them.
+    pub conference_data_version: Option<u8>,
+    pub supports_attachments: Option<bool>,
+    pub max_attendees: Option<i32>,
+}
+
+impl EventRequestOptions {
+    /// Notify guests according to `send_updates`.
+    pub fn send_updates(send_updates: SendUpdates) -> Self {
+        Self {
+            send_updates: Some(send_updates),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn apply(options: Option<Self>, event: &mut Event) -> ClientResult<()> {
+        if let Some(options) = options {
+            for (key, value) in options_query(&options)? {
+                event.add_query(key, value);
+            }
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+api_enum! {
+    pub enum EventTypes {
+        Birthday => "birthday",
+        #[default]
+        Default => "default",
+        FocusTime => "focusTime",
+        FromGmail => "fromGmail",
+        OutOfOffice => "outOfOffice",
+        WorkingLocation => "workingLocation",
+    }
+}
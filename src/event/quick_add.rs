@@ -0,0 +1,396 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, Month, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
+
+use super::{recurrence::resolve_local, ClientResult, Event, EventCalendarDate};
+
+/// QuickAddPreview is a local guess of what Google's quickAdd will create from a piece of text.
+/// It understands a subset of the phrases quickAdd does, e.g.
+/// `Lunch with Sam at Cafe Roma tomorrow at noon` or `Standup mon 9:30-9:45am`, so that the
+/// proposal can be confirmed before anything is created. Google's parser remains authoritative.
+///
+/// Recognised phrases:
+/// - dates: `today`, `tomorrow`, weekdays (`friday`, `next fri`), `jan 5`, `5th march 2025`,
+///   `2025-01-05` and `1/5` (month first), optionally preceded by `on`
+/// - times: `3pm`, `3:30 pm`, `15:00`, `noon`, `midnight`, `at 7`, optionally a range such as
+///   `3-4pm`, `from 9 to 11` or `2pm until 4`
+/// - durations: `for 2 hours`, `for 30 min`, `for an hour`, `for 3 days`
+/// - locations: `at <place>` when the words after `at` are not a time
+///
+/// Text without a time becomes an all-day event, text without a date happens today and events
+/// without an end last an hour. Amounts that are not positive numbers or too large for a
+/// `Duration`, such as `for inf hours`, are left in the summary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickAddPreview {
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    pub all_day: bool,
+}
+
+impl QuickAddPreview {
+    /// Parse `text` relative to `now`, whose time zone the event is placed in. Returns `None`
+    /// when the event would end beyond the dates chrono can represent.
+    pub fn parse(text: &str, now: &DateTime<Tz>) -> Option<Self> {
+        let original: Vec<&str> = text.split_whitespace().collect();
+        let words: Vec<String> = original
+            .iter()
+            .map(|w| w.trim_end_matches([',', '.', ';']).to_lowercase())
+            .collect();
+        let today = now.date_naive();
+
+        let mut date = None;
+        let mut time = None;
+        let mut duration = None;
+        let mut location = None;
+        let mut summary = Vec::new();
+
+        let mut i = 0;
+        while i < words.len() {
+            let rest = &words[i..];
+            if date.is_none() {
+                if let Some((value, len)) = match_date(rest, today) {
+                    date = Some(value);
+                    i += len;
+                    continue;
+                }
+            }
+            if time.is_none() {
+                if let Some((value, len)) = match_time(rest) {
+                    time = Some(value);
+                    i += len;
+                    continue;
+                }
+            }
+            if duration.is_none() {
+                if let Some((value, len)) = match_duration(rest) {
+                    duration = Some(value);
+                    i += len;
+                    continue;
+                }
+            }
+            if location.is_none() && rest[0] == "at" && rest.len() > 1 {
+                let len = 1
+                    + (1..rest.len())
+                        .take_while(|&j| !starts_expression(&rest[j..], today))
+                        .count();
+                if len > 1 {
+                    let place = original[i + 1..i + len].join(" ");
+                    location = Some(place.trim_end_matches([',', '.', ';']).to_string());
+                    i += len;
+                    continue;
+                }
+            }
+            summary.push(original[i]);
+            i += 1;
+        }
+
+        let tz = now.timezone();
+        let date = date.unwrap_or(today);
+        let (start, end, all_day) = match time {
+            Some((start_time, end_time)) => {
+                let start = resolve_local(&tz, date.and_time(start_time));
+                let end = match (end_time, duration) {
+                    (Some(end_time), _) => {
+                        let end_date = if end_time <= start_time {
+                            date.succ_opt()?
+                        } else {
+                            date
+                        };
+                        resolve_local(&tz, end_date.and_time(end_time))
+                    }
+                    (None, Some(duration)) => start.checked_add_signed(duration)?,
+                    (None, None) => start.checked_add_signed(Duration::hours(1))?,
+                };
+                (start, end, false)
+            }
+            None => {
+                let days = duration.map_or(1, |d: Duration| d.num_days().max(1));
+                let start = resolve_local(&tz, date.and_time(NaiveTime::MIN));
+                let end_date = date.checked_add_signed(Duration::try_days(days)?)?;
+                let end = resolve_local(&tz, end_date.and_time(NaiveTime::MIN));
+                (start, end, true)
+            }
+        };
+
+        Some(Self {
+            summary: summary
+                .join(" ")
+                .trim_end_matches([',', '.', ';'])
+                .to_string(),
+            location,
+            start,
+            end,
+            all_day,
+        })
+    }
+
+    /// The event the preview describes, built with `EventBuilder` and ready for
+    /// `EventClient::insert`.
+    pub fn to_event(&self) -> ClientResult<Event> {
+        let date = |dt: &DateTime<Tz>| {
+            if self.all_day {
+                EventCalendarDate::from_date(dt.date_naive())
+            } else {
+                EventCalendarDate::from_datetime(dt).with_time_zone(dt.timezone().name())
+            }
+        };
+        let mut builder = Event::builder(&self.summary)
+            .start(date(&self.start))
+            .end(date(&self.end));
+        if let Some(location) = &self.location {
+            builder = builder.location(location);
+        }
+        builder.build()
+    }
+}
+
+/// Whether a date, time or duration begins at the start of `words`.
+fn starts_expression(words: &[String], today: NaiveDate) -> bool {
+    match_date(words, today).is_some()
+        || match_time(words).is_some()
+        || match_duration(words).is_some()
+}
+
+fn match_date(words: &[String], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let skip = usize::from(words.first()? == "on");
+    let (date, len) = match_bare_date(&words[skip..], today)?;
+    Some((date, skip + len))
+}
+
+fn match_bare_date(words: &[String], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let word = words.first()?.as_str();
+    match word {
+        "today" => return Some((today, 1)),
+        "tomorrow" => return Some((today.succ_opt()?, 1)),
+        "next" => {
+            let weekday = Weekday::from_str(words.get(1)?).ok()?;
+            let ahead = days_until(today.weekday(), weekday);
+            let ahead = if ahead == 0 { 7 } else { ahead };
+            return Some((today.checked_add_signed(Duration::days(ahead))?, 2));
+        }
+        _ => {}
+    }
+    if let Ok(weekday) = Weekday::from_str(word) {
+        let ahead = Duration::days(days_until(today.weekday(), weekday));
+        return Some((today.checked_add_signed(ahead)?, 1));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some((date, 1));
+    }
+    if let Some(date) = match_slash_date(word, today) {
+        return Some((date, 1));
+    }
+
+    // "jan 5", "january 5th 2025", "5 jan", "5th of january"
+    let (month, day, len) = if let Ok(month) = Month::from_str(word) {
+        (month, parse_day(words.get(1)?)?, 2)
+    } else {
+        let day = parse_day(word)?;
+        let skip = usize::from(words.get(1).map(String::as_str) == Some("of"));
+        (Month::from_str(words.get(1 + skip)?).ok()?, day, 2 + skip)
+    };
+    let month = month.number_from_month();
+    match words.get(len).and_then(|y| parse_year(y)) {
+        Some(year) => Some((NaiveDate::from_ymd_opt(year, month, day)?, len + 1)),
+        None => Some((upcoming(today, month, day)?, len)),
+    }
+}
+
+/// `m/d` or `m/d/yyyy`.
+fn match_slash_date(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let parts = word
+        .split('/')
+        .map(|p| p.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts[..] {
+        [month, day] => upcoming(today, month, day),
+        [month, day, year] => NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day),
+        _ => None,
+    }
+}
+
+/// The next `month`/`day` on or after `today`.
+fn upcoming(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    match NaiveDate::from_ymd_opt(today.year(), month, day) {
+        Some(date) if date >= today => Some(date),
+        _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day),
+    }
+}
+
+fn days_until(from: Weekday, to: Weekday) -> i64 {
+    (to.num_days_from_monday() as i64 - from.num_days_from_monday() as i64).rem_euclid(7)
+}
+
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|s| word.strip_suffix(s))
+        .unwrap_or(word);
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn parse_year(word: &str) -> Option<i32> {
+    (word.len() == 4).then(|| word.parse().ok())?
+}
+
+/// A clock reading before `am`/`pm` is applied.
+#[derive(Clone, Copy)]
+struct Clock {
+    hour: u32,
+    minute: u32,
+    pm: Option<bool>,
+    /// Written with minutes, e.g. `15:00`, and therefore not a bare number.
+    has_minutes: bool,
+}
+
+impl Clock {
+    fn hour24(&self) -> u32 {
+        match self.pm {
+            Some(true) => self.hour % 12 + 12,
+            Some(false) => self.hour % 12,
+            None => self.hour,
+        }
+    }
+
+    fn time(&self, hour: u32) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(hour, self.minute, 0)
+    }
+}
+
+fn parse_clock(word: &str) -> Option<Clock> {
+    match word {
+        "noon" => return clock(12, 0, Some(true), true),
+        "midnight" => return clock(12, 0, Some(false), true),
+        _ => {}
+    }
+    let (digits, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(d), _) => (d, Some(false)),
+        (_, Some(d)) => (d, Some(true)),
+        _ => (word, None),
+    };
+    let (hour, minute, has_minutes) = match digits.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse().ok()?, m.parse().ok()?, true),
+        Some(_) => return None,
+        None => (digits.parse().ok()?, 0, false),
+    };
+    clock(hour, minute, pm, has_minutes)
+}
+
+fn clock(hour: u32, minute: u32, pm: Option<bool>, has_minutes: bool) -> Option<Clock> {
+    let valid_hour = match pm {
+        Some(_) => (1..=12).contains(&hour),
+        None => hour <= 23,
+    };
+    (valid_hour && minute <= 59).then_some(Clock {
+        hour,
+        minute,
+        pm,
+        has_minutes,
+    })
+}
+
+/// A clock reading at the start of `words`, merging a separate `am`/`pm` word.
+fn match_clock(words: &[String]) -> Option<(Clock, usize)> {
+    let mut clock = parse_clock(words.first()?)?;
+    if clock.pm.is_none() {
+        let pm = match words.get(1).map(String::as_str) {
+            Some("am") => Some(false),
+            Some("pm") => Some(true),
+            _ => None,
+        };
+        if pm.is_some() && (1..=12).contains(&clock.hour) {
+            clock.pm = pm;
+            return Some((clock, 2));
+        }
+    }
+    Some((clock, 1))
+}
+
+fn match_time(words: &[String]) -> Option<((NaiveTime, Option<NaiveTime>), usize)> {
+    let prefixed = matches!(words.first()?.as_str(), "at" | "from" | "@");
+    let skip = usize::from(prefixed);
+    let rest = &words[skip..];
+
+    // "3-4pm" written as a single word.
+    let (start, end, len) = match rest.first()?.split_once('-') {
+        Some((a, b)) => (parse_clock(a)?, Some(parse_clock(b)?), 1),
+        None => {
+            let (start, len) = match_clock(rest)?;
+            match rest.get(len).map(String::as_str) {
+                Some("-" | "to" | "until" | "till") => match match_clock(&rest[len + 1..]) {
+                    Some((end, end_len)) => (start, Some(end), len + 1 + end_len),
+                    None => (start, None, len),
+                },
+                _ => (start, None, len),
+            }
+        }
+    };
+    // A bare number is only a time when introduced by "at"/"from" or part of a range.
+    if start.pm.is_none() && !start.has_minutes && !prefixed && end.is_none() {
+        return None;
+    }
+
+    let start_hour = match (start.pm, end.and_then(|e| e.pm)) {
+        (Some(_), _) => start.hour24(),
+        // "11-1pm" starts in the morning, "3-4pm" in the afternoon.
+        (None, Some(end_pm)) if (1..=12).contains(&start.hour) => {
+            let hour = Clock {
+                pm: Some(end_pm),
+                ..start
+            }
+            .hour24();
+            let end_hour = end.map_or(0, |e| e.hour24());
+            if hour > end_hour && hour >= 12 {
+                hour - 12
+            } else {
+                hour
+            }
+        }
+        // "at 7" means the evening, "at 9" the morning.
+        (None, _) if !start.has_minutes && (1..=7).contains(&start.hour) => start.hour + 12,
+        (None, _) => start.hour,
+    };
+    let end = match end {
+        Some(end) => {
+            let mut hour = end.hour24();
+            // "2pm to 4" ends in the afternoon.
+            if end.pm.is_none() && hour < 12 && hour < start_hour && hour + 12 > start_hour {
+                hour += 12;
+            }
+            Some(end.time(hour)?)
+        }
+        None => None,
+    };
+    Some(((start.time(start_hour)?, end), skip + len))
+}
+
+fn match_duration(words: &[String]) -> Option<(Duration, usize)> {
+    if words.first()? != "for" {
+        return None;
+    }
+    let (amount, unit, len) = match words.get(1)?.as_str() {
+        "a" | "an" | "one" => (1.0, words.get(2)?.as_str(), 3),
+        word => match word.parse::<f64>() {
+            Ok(amount) => (amount, words.get(2)?.as_str(), 3),
+            Err(_) => {
+                // "2h", "30min"
+                let split = word.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+                (word[..split].parse().ok()?, &word[split..], 2)
+            }
+        },
+    };
+    let minutes = match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => 1.0,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60.0,
+        "d" | "day" | "days" => 1440.0,
+        _ => return None,
+    };
+    let minutes = amount * minutes;
+    if !minutes.is_finite() || minutes < 1.0 || minutes > i64::MAX as f64 {
+        return None;
+    }
+    Some((Duration::try_minutes(minutes.round() as i64)?, len))
+}
@@ -0,0 +1,895 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use super::{ClientError, ClientResult};
+
+/* RFC 5545 Source: https://datatracker.ietf.org/doc/html/rfc5545#section-3.3.10 */
+
+/// Frequency is the FREQ part of a recurrence rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+impl Frequency {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Self::Secondly => "SECONDLY",
+            Self::Minutely => "MINUTELY",
+            Self::Hourly => "HOURLY",
+            Self::Daily => "DAILY",
+            Self::Weekly => "WEEKLY",
+            Self::Monthly => "MONTHLY",
+            Self::Yearly => "YEARLY",
+        }
+    }
+}
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+impl FromStr for Frequency {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> ClientResult<Self> {
+        Ok(match s {
+            "SECONDLY" => Self::Secondly,
+            "MINUTELY" => Self::Minutely,
+            "HOURLY" => Self::Hourly,
+            "DAILY" => Self::Daily,
+            "WEEKLY" => Self::Weekly,
+            "MONTHLY" => Self::Monthly,
+            "YEARLY" => Self::Yearly,
+            _ => return Err(invalid(format!("unknown frequency `{}`", s))),
+        })
+    }
+}
+
+/// WeekdayNum is a single BYDAY entry, e.g. `MO`, `1FR` or `-1SU`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WeekdayNum {
+    pub ordinal: Option<i8>,
+    pub weekday: Weekday,
+}
+impl WeekdayNum {
+    /// Every occurrence of the weekday within the period.
+    pub fn every(weekday: Weekday) -> Self {
+        Self {
+            ordinal: None,
+            weekday,
+        }
+    }
+    /// The nth occurrence of the weekday within the month or year; negative values count from
+    /// the end.
+    pub fn nth(ordinal: i8, weekday: Weekday) -> Self {
+        Self {
+            ordinal: Some(ordinal),
+            weekday,
+        }
+    }
+}
+impl fmt::Display for WeekdayNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(n) = self.ordinal {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "{}", weekday_str(self.weekday))
+    }
+}
+impl FromStr for WeekdayNum {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> ClientResult<Self> {
+        if s.len() < 2 || !s.is_char_boundary(s.len() - 2) {
+            return Err(invalid(format!("invalid weekday `{}`", s)));
+        }
+        let (n, wd) = s.split_at(s.len() - 2);
+        let ordinal = if n.is_empty() {
+            None
+        } else {
+            let n = n
+                .trim_start_matches('+')
+                .parse::<i8>()
+                .map_err(|_| invalid(format!("invalid weekday ordinal `{}`", s)))?;
+            if n == 0 || !(-53..=53).contains(&n) {
+                return Err(invalid(format!("weekday ordinal out of range `{}`", s)));
+            }
+            Some(n)
+        };
+        Ok(Self {
+            ordinal,
+            weekday: parse_weekday(wd)?,
+        })
+    }
+}
+
+/// RecurrenceDate is a value of UNTIL, EXDATE or RDATE. The variant mirrors the RFC 5545 forms:
+/// a plain date, a floating local time, a UTC time or a time bound to a TZID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceDate {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Utc(DateTime<Utc>),
+    Zoned(DateTime<Tz>),
+}
+impl RecurrenceDate {
+    /// Parse a single value, optionally bound to a TZID parameter.
+    pub fn parse(value: &str, tzid: Option<Tz>) -> ClientResult<Self> {
+        let err = || invalid(format!("invalid date value `{}`", value));
+        if value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(Self::Date)
+                .map_err(|_| err());
+        }
+        if let Some(value) = value.strip_suffix('Z') {
+            return NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                .map(|dt| Self::Utc(dt.and_utc()))
+                .map_err(|_| err());
+        }
+        let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| err())?;
+        Ok(match tzid {
+            Some(tz) => Self::Zoned(resolve_local(&tz, local)),
+            None => Self::Floating(local),
+        })
+    }
+
+    /// Resolve the value to an instant in the given time zone. Plain dates take the time of day
+    /// of `reference`, floating times are interpreted as wall-clock time in `tz`.
+    pub fn resolve(&self, tz: &Tz, reference: NaiveTime) -> DateTime<Tz> {
+        match self {
+            Self::Date(d) => resolve_local(tz, d.and_time(reference)),
+            Self::Floating(dt) => resolve_local(tz, *dt),
+            Self::Utc(dt) => dt.with_timezone(tz),
+            Self::Zoned(dt) => dt.with_timezone(tz),
+        }
+    }
+
+    /// Format the value as a full EXDATE or RDATE line, keeping the TZID of zoned values.
+    pub fn to_line(&self, name: &str) -> String {
+        match self {
+            Self::Date(_) => format!("{};VALUE=DATE:{}", name, self),
+            Self::Zoned(dt) => format!(
+                "{};TZID={}:{}",
+                name,
+                dt.timezone().name(),
+                dt.naive_local().format("%Y%m%dT%H%M%S")
+            ),
+            _ => format!("{}:{}", name, self),
+        }
+    }
+
+    /// Whether the occurrence falls on or before this bound. Plain dates compare by the local
+    /// date of the occurrence.
+    fn is_after_or_at(&self, occ: &DateTime<Tz>) -> bool {
+        match self {
+            Self::Date(d) => occ.date_naive() <= *d,
+            Self::Floating(dt) => occ.naive_local() <= *dt,
+            Self::Utc(dt) => occ.with_timezone(&Utc) <= *dt,
+            Self::Zoned(dt) => occ <= dt,
+        }
+    }
+
+    /// Whether the occurrence is the one this value refers to. Plain dates match any occurrence
+    /// on that local date.
+    fn matches(&self, occ: &DateTime<Tz>) -> bool {
+        match self {
+            Self::Date(d) => occ.date_naive() == *d,
+            Self::Floating(dt) => occ.naive_local() == *dt,
+            Self::Utc(dt) => occ.with_timezone(&Utc) == *dt,
+            Self::Zoned(dt) => occ == dt,
+        }
+    }
+}
+impl fmt::Display for RecurrenceDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Date(d) => write!(f, "{}", d.format("%Y%m%d")),
+            Self::Floating(dt) => write!(f, "{}", dt.format("%Y%m%dT%H%M%S")),
+            Self::Utc(dt) => write!(f, "{}", dt.format("%Y%m%dT%H%M%SZ")),
+            Self::Zoned(dt) => write!(f, "{}", dt.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+/// RecurrenceRule is a typed RRULE line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<RecurrenceDate>,
+    pub by_second: Vec<u8>,
+    pub by_minute: Vec<u8>,
+    pub by_hour: Vec<u8>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i8>,
+    pub by_year_day: Vec<i16>,
+    pub by_week_no: Vec<i8>,
+    pub by_month: Vec<u8>,
+    pub by_set_pos: Vec<i16>,
+    pub week_start: Weekday,
+}
+
+impl RecurrenceRule {
+    /// A rule repeating at the given frequency every period, without any limits.
+    pub fn new(freq: Frequency) -> Self {
+        Self {
+            freq,
+            interval: 1,
+            count: None,
+            until: None,
+            by_second: Vec::new(),
+            by_minute: Vec::new(),
+            by_hour: Vec::new(),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_year_day: Vec::new(),
+            by_week_no: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        }
+    }
+
+    /// Every day.
+    pub fn daily() -> Self {
+        Self::new(Frequency::Daily)
+    }
+
+    /// Every week on the given days, e.g. `weekly_on(&[Weekday::Mon, Weekday::Wed])`.
+    pub fn weekly_on(days: &[Weekday]) -> Self {
+        Self {
+            by_day: days.iter().map(|d| WeekdayNum::every(*d)).collect(),
+            ..Self::new(Frequency::Weekly)
+        }
+    }
+
+    /// Every month on the nth weekday, e.g. `monthly_by_weekday(-1, Weekday::Fri)` for the last
+    /// Friday of each month.
+    pub fn monthly_by_weekday(nth: i8, weekday: Weekday) -> Self {
+        Self {
+            by_day: vec![WeekdayNum::nth(nth, weekday)],
+            ..Self::new(Frequency::Monthly)
+        }
+    }
+
+    /// Repeat every `interval` periods instead of every period.
+    pub fn with_interval(mut self, interval: u32) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    /// Stop after `count` occurrences. Replaces any UNTIL. A COUNT of 0 is rejected, a series
+    /// always has at least one occurrence.
+    pub fn with_count(mut self, count: u32) -> ClientResult<Self> {
+        if count == 0 {
+            return Err(invalid("COUNT must be at least 1".to_string()));
+        }
+        self.count = Some(count);
+        self.until = None;
+        Ok(self)
+    }
+
+    /// Stop after the given date. Replaces any COUNT.
+    pub fn with_until(mut self, until: RecurrenceDate) -> Self {
+        self.until = Some(until);
+        self.count = None;
+        self
+    }
+
+    /// Expand the rule into occurrences starting at `dtstart`. Occurrences are generated in the
+    /// time zone of `dtstart` so that wall-clock times stay stable across DST transitions.
+    /// Generation stops at the first occurrence at or after `end`, or when COUNT or UNTIL is
+    /// exhausted. `dtstart` always counts as the first occurrence.
+    pub fn occurrences(&self, dtstart: &DateTime<Tz>, end: &DateTime<Tz>) -> Vec<DateTime<Tz>> {
+        let tz = dtstart.timezone();
+        let start = dtstart.naive_local();
+        let interval = self.interval.max(1) as i64;
+        let sets = ExpandSets::new(self, start);
+
+        let mut out = vec![*dtstart];
+        let limit = |out: &Vec<DateTime<Tz>>| self.count.is_some_and(|c| out.len() >= c as usize);
+        if limit(&out) {
+            return out;
+        }
+
+        for k in 0.. {
+            let Some((first, days)) = self.period(start, k * interval) else {
+                break;
+            };
+            // The latest UTC offset is +14:00, anything later than that is past the window.
+            if resolve_local(&tz, first) >= *end + Duration::days(1) {
+                break;
+            }
+
+            let mut candidates: Vec<NaiveDateTime> = days
+                .into_iter()
+                .filter(|d| self.day_matches(*d, &sets))
+                .flat_map(|d| {
+                    sets.times(self.freq, first)
+                        .into_iter()
+                        .map(move |t| d.and_time(t))
+                })
+                .collect();
+            candidates.sort();
+            candidates.dedup();
+            if !self.by_set_pos.is_empty() {
+                candidates = select_positions(&candidates, &self.by_set_pos);
+            }
+
+            for candidate in candidates {
+                if candidate <= start {
+                    continue;
+                }
+                let occ = resolve_local(&tz, candidate);
+                if occ >= *end || self.until.is_some_and(|u| !u.is_after_or_at(&occ)) {
+                    return out;
+                }
+                if out.last() != Some(&occ) {
+                    out.push(occ);
+                }
+                if limit(&out) {
+                    return out;
+                }
+            }
+        }
+        out
+    }
+
+    /// The first instant of the kth period and the days it covers.
+    fn period(&self, start: NaiveDateTime, k: i64) -> Option<(NaiveDateTime, Vec<NaiveDate>)> {
+        let date = start.date();
+        Some(match self.freq {
+            // Week numbers belong to the week-numbering year, which can start in late December
+            // and end in early January.
+            Frequency::Yearly if !self.by_week_no.is_empty() => {
+                let year = week_number(date, self.week_start).0 as i64 + k;
+                let year = i32::try_from(year).ok()?;
+                let first = week_one_start(year, self.week_start);
+                let next = week_one_start(year.checked_add(1)?, self.week_start);
+                (
+                    first.and_time(NaiveTime::MIN),
+                    first.iter_days().take_while(|d| *d < next).collect(),
+                )
+            }
+            Frequency::Yearly => {
+                let year = i32::try_from(date.year() as i64 + k).ok()?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let last = NaiveDate::from_ymd_opt(year, 12, 31)?;
+                (
+                    first.and_time(NaiveTime::MIN),
+                    first.iter_days().take_while(|d| *d <= last).collect(),
+                )
+            }
+            Frequency::Monthly => {
+                let months = date.year() as i64 * 12 + date.month0() as i64 + k;
+                let first = NaiveDate::from_ymd_opt(
+                    i32::try_from(months / 12).ok()?,
+                    (months % 12) as u32 + 1,
+                    1,
+                )?;
+                let days = first
+                    .iter_days()
+                    .take_while(|d| d.month() == first.month())
+                    .collect();
+                (first.and_time(NaiveTime::MIN), days)
+            }
+            Frequency::Weekly => {
+                let offset = days_between(self.week_start, date.weekday());
+                let first = date
+                    .checked_sub_signed(Duration::days(offset))?
+                    .checked_add_signed(Duration::weeks(k))?;
+                (
+                    first.and_time(NaiveTime::MIN),
+                    first.iter_days().take(7).collect(),
+                )
+            }
+            Frequency::Daily => {
+                let day = date.checked_add_signed(Duration::days(k))?;
+                (day.and_time(NaiveTime::MIN), vec![day])
+            }
+            Frequency::Hourly | Frequency::Minutely | Frequency::Secondly => {
+                let step = match self.freq {
+                    Frequency::Hourly => Duration::hours(k),
+                    Frequency::Minutely => Duration::minutes(k),
+                    _ => Duration::seconds(k),
+                };
+                let first = start.checked_add_signed(step)?;
+                (first, vec![first.date()])
+            }
+        })
+    }
+
+    fn day_matches(&self, d: NaiveDate, sets: &ExpandSets) -> bool {
+        if !sets.by_month.is_empty() && !sets.by_month.contains(&(d.month() as u8)) {
+            return false;
+        }
+        if !self.by_week_no.is_empty() {
+            let (_, week, weeks) = week_number(d, self.week_start);
+            let matches = self
+                .by_week_no
+                .iter()
+                .any(|&n| (n > 0 && n as i64 == week) || (n < 0 && weeks + n as i64 + 1 == week));
+            if !matches {
+                return false;
+            }
+        }
+        if !self.by_year_day.is_empty() {
+            let days = days_in_year(d.year());
+            let day = d.ordinal() as i64;
+            let matches = self
+                .by_year_day
+                .iter()
+                .any(|&n| (n > 0 && n as i64 == day) || (n < 0 && days + n as i64 + 1 == day));
+            if !matches {
+                return false;
+            }
+        }
+        if !sets.by_month_day.is_empty() {
+            let days = days_in_month(d);
+            let day = d.day() as i64;
+            let matches = sets
+                .by_month_day
+                .iter()
+                .any(|&n| (n > 0 && n as i64 == day) || (n < 0 && days + n as i64 + 1 == day));
+            if !matches {
+                return false;
+            }
+        }
+        if !sets.by_day.is_empty() {
+            let (first, last) = match self.freq {
+                Frequency::Yearly if self.by_month.is_empty() => (
+                    NaiveDate::from_ymd_opt(d.year(), 1, 1),
+                    NaiveDate::from_ymd_opt(d.year(), 12, 31),
+                ),
+                Frequency::Yearly | Frequency::Monthly => {
+                    (d.with_day(1), d.with_day(days_in_month(d) as u32))
+                }
+                _ => (None, None),
+            };
+            let matches = sets.by_day.iter().any(|wd| {
+                if wd.weekday != d.weekday() {
+                    return false;
+                }
+                // Ordinals only have a meaning within a month or a year.
+                match (wd.ordinal, first, last) {
+                    (Some(n), Some(first), _) if n > 0 => {
+                        (d - first).num_days() / 7 + 1 == n as i64
+                    }
+                    (Some(n), _, Some(last)) => (last - d).num_days() / 7 + 1 == -(n as i64),
+                    _ => true,
+                }
+            });
+            if !matches {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, key: &str, v: &[T]) -> fmt::Result {
+            if v.is_empty() {
+                return Ok(());
+            }
+            let v: Vec<String> = v.iter().map(|x| x.to_string()).collect();
+            write!(f, ";{}={}", key, v.join(","))
+        }
+
+        write!(f, "RRULE:FREQ={}", self.freq)?;
+        if let Some(until) = &self.until {
+            write!(f, ";UNTIL={}", until)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        list(f, "BYSECOND", &self.by_second)?;
+        list(f, "BYMINUTE", &self.by_minute)?;
+        list(f, "BYHOUR", &self.by_hour)?;
+        list(f, "BYDAY", &self.by_day)?;
+        list(f, "BYMONTHDAY", &self.by_month_day)?;
+        list(f, "BYYEARDAY", &self.by_year_day)?;
+        list(f, "BYWEEKNO", &self.by_week_no)?;
+        list(f, "BYMONTH", &self.by_month)?;
+        list(f, "BYSETPOS", &self.by_set_pos)?;
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_str(self.week_start))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> ClientResult<Self> {
+        fn list<T: FromStr>(
+            key: &str,
+            value: &str,
+            range: impl Fn(&T) -> bool,
+        ) -> ClientResult<Vec<T>> {
+            value
+                .split(',')
+                .map(|v| {
+                    v.trim_start_matches('+')
+                        .parse::<T>()
+                        .ok()
+                        .filter(|v| range(v))
+                        .ok_or_else(|| invalid(format!("invalid {} value `{}`", key, v)))
+                })
+                .collect()
+        }
+
+        let body = s.strip_prefix("RRULE:").unwrap_or(s);
+        let mut freq = None;
+        let mut rule = Self::new(Frequency::Daily);
+        for part in body.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("invalid rule part `{}`", part)))?;
+            match key {
+                "FREQ" => freq = Some(value.parse()?),
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|i| *i > 0)
+                        .ok_or_else(|| invalid(format!("invalid INTERVAL `{}`", value)))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|c| *c > 0)
+                            .ok_or_else(|| invalid(format!("invalid COUNT `{}`", value)))?,
+                    )
+                }
+                "UNTIL" => rule.until = Some(RecurrenceDate::parse(value, None)?),
+                "BYSECOND" => rule.by_second = list(key, value, |v| *v <= 60)?,
+                "BYMINUTE" => rule.by_minute = list(key, value, |v| *v <= 59)?,
+                "BYHOUR" => rule.by_hour = list(key, value, |v| *v <= 23)?,
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<ClientResult<_>>()?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day =
+                        list(key, value, |v: &i8| *v != 0 && (-31..=31).contains(v))?
+                }
+                "BYYEARDAY" => {
+                    rule.by_year_day =
+                        list(key, value, |v: &i16| *v != 0 && (-366..=366).contains(v))?
+                }
+                "BYWEEKNO" => {
+                    rule.by_week_no = list(key, value, |v: &i8| *v != 0 && (-53..=53).contains(v))?
+                }
+                "BYMONTH" => rule.by_month = list(key, value, |v| (1..=12).contains(v))?,
+                "BYSETPOS" => {
+                    rule.by_set_pos =
+                        list(key, value, |v: &i16| *v != 0 && (-366..=366).contains(v))?
+                }
+                "WKST" => rule.week_start = parse_weekday(value)?,
+                _ => return Err(invalid(format!("unsupported rule part `{}`", key))),
+            }
+        }
+        rule.freq = freq.ok_or_else(|| invalid("missing FREQ".to_string()))?;
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err(invalid(
+                "COUNT and UNTIL are mutually exclusive".to_string(),
+            ));
+        }
+        Ok(rule)
+    }
+}
+
+/// Recurrence is the parsed form of an event's `recurrence` lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recurrence {
+    pub rules: Vec<RecurrenceRule>,
+    pub exdates: Vec<RecurrenceDate>,
+    pub rdates: Vec<RecurrenceDate>,
+}
+
+impl Recurrence {
+    /// Parse RRULE, EXDATE and RDATE lines as they appear in `Event::recurrence`.
+    pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> ClientResult<Self> {
+        let mut recurrence = Self::default();
+        for line in lines {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("invalid recurrence line `{}`", line)))?;
+            let mut params = name.split(';');
+            match params.next() {
+                Some("RRULE") => recurrence.rules.push(value.parse()?),
+                Some(kind @ ("EXDATE" | "RDATE")) => {
+                    let dates = parse_dates(params, value)?;
+                    if kind == "EXDATE" {
+                        recurrence.exdates.extend(dates);
+                    } else {
+                        recurrence.rdates.extend(dates);
+                    }
+                }
+                _ => return Err(invalid(format!("unsupported recurrence line `{}`", line))),
+            }
+        }
+        Ok(recurrence)
+    }
+
+    /// All occurrence start times of a series starting at `dtstart` that fall within
+    /// `[from, to)`, sorted and with EXDATEs removed.
+    pub fn between(
+        &self,
+        dtstart: &DateTime<Tz>,
+        from: &DateTime<Tz>,
+        to: &DateTime<Tz>,
+    ) -> Vec<DateTime<Tz>> {
+        let tz = dtstart.timezone();
+        let time = dtstart.naive_local().time();
+
+        let mut out: Vec<DateTime<Tz>> = if self.rules.is_empty() {
+            vec![*dtstart]
+        } else {
+            self.rules
+                .iter()
+                .flat_map(|r| r.occurrences(dtstart, to))
+                .collect()
+        };
+        out.extend(self.rdates.iter().map(|d| d.resolve(&tz, time)));
+        out.retain(|occ| occ >= from && occ < to && !self.exdates.iter().any(|ex| ex.matches(occ)));
+        out.sort();
+        out.dedup();
+        out
+    }
+}
+
+/// Occurrence is a single concrete instance of an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    pub all_day: bool,
+}
+
+/// Effective BYxxx sets after applying the RFC 5545 defaults taken from DTSTART.
+struct ExpandSets {
+    by_month: Vec<u8>,
+    by_month_day: Vec<i8>,
+    by_day: Vec<WeekdayNum>,
+    by_hour: Vec<u8>,
+    by_minute: Vec<u8>,
+    by_second: Vec<u8>,
+    explicit_hour: bool,
+    explicit_minute: bool,
+    explicit_second: bool,
+}
+
+impl ExpandSets {
+    fn new(rule: &RecurrenceRule, start: NaiveDateTime) -> Self {
+        let mut by_month = rule.by_month.clone();
+        let mut by_month_day = rule.by_month_day.clone();
+        let mut by_day = rule.by_day.clone();
+        let no_day_rules = rule.by_week_no.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_day.is_empty();
+
+        match rule.freq {
+            Frequency::Yearly if no_day_rules => {
+                if by_month.is_empty() {
+                    by_month.push(start.month() as u8);
+                }
+                by_month_day.push(start.day() as i8);
+            }
+            Frequency::Monthly if no_day_rules => by_month_day.push(start.day() as i8),
+            Frequency::Weekly if no_day_rules => by_day.push(WeekdayNum::every(start.weekday())),
+            _ => {}
+        }
+
+        let or_default = |v: &Vec<u8>, freq: Frequency, x: u32| {
+            if v.is_empty() && rule.freq > freq {
+                vec![x as u8]
+            } else {
+                v.clone()
+            }
+        };
+        Self {
+            by_month,
+            by_month_day,
+            by_day,
+            by_hour: or_default(&rule.by_hour, Frequency::Hourly, start.hour()),
+            by_minute: or_default(&rule.by_minute, Frequency::Minutely, start.minute()),
+            by_second: or_default(&rule.by_second, Frequency::Secondly, start.second()),
+            explicit_hour: !rule.by_hour.is_empty(),
+            explicit_minute: !rule.by_minute.is_empty(),
+            explicit_second: !rule.by_second.is_empty(),
+        }
+    }
+
+    /// Times of day within a period. For sub-daily frequencies the period's own hour, minute or
+    /// second is used, limited by any explicit BYxxx part.
+    fn times(&self, freq: Frequency, first: NaiveDateTime) -> Vec<NaiveTime> {
+        let pick = |set: &Vec<u8>, explicit: bool, own: u32, period: bool| -> Vec<u32> {
+            if period {
+                if explicit && !set.contains(&(own as u8)) {
+                    vec![]
+                } else {
+                    vec![own]
+                }
+            } else {
+                set.iter().map(|x| *x as u32).collect()
+            }
+        };
+        let hours = pick(
+            &self.by_hour,
+            self.explicit_hour,
+            first.hour(),
+            freq <= Frequency::Hourly,
+        );
+        let minutes = pick(
+            &self.by_minute,
+            self.explicit_minute,
+            first.minute(),
+            freq <= Frequency::Minutely,
+        );
+        let seconds = pick(
+            &self.by_second,
+            self.explicit_second,
+            first.second(),
+            freq <= Frequency::Secondly,
+        );
+
+        let mut times = Vec::new();
+        for h in &hours {
+            for m in &minutes {
+                for s in &seconds {
+                    // Leap seconds (60) are clamped, chrono cannot represent them.
+                    if let Some(t) = NaiveTime::from_hms_opt(*h, *m, (*s).min(59)) {
+                        times.push(t);
+                    }
+                }
+            }
+        }
+        times.sort();
+        times
+    }
+}
+
+fn parse_dates<'a>(
+    params: impl Iterator<Item = &'a str>,
+    value: &str,
+) -> ClientResult<Vec<RecurrenceDate>> {
+    let mut tzid = None;
+    for param in params {
+        if let Some(id) = param.strip_prefix("TZID=") {
+            tzid = Some(
+                id.parse::<Tz>()
+                    .map_err(|_| invalid(format!("unknown TZID `{}`", id)))?,
+            );
+        }
+    }
+    value
+        .split(',')
+        // PERIOD values are `start/end` or `start/duration`, only the start matters here.
+        .map(|v| RecurrenceDate::parse(v.split('/').next().unwrap_or(v), tzid))
+        .collect()
+}
+
+fn select_positions(candidates: &[NaiveDateTime], positions: &[i16]) -> Vec<NaiveDateTime> {
+    let len = candidates.len() as i64;
+    let mut out: Vec<NaiveDateTime> = positions
+        .iter()
+        .filter_map(|&p| {
+            let idx = if p > 0 { p as i64 - 1 } else { len + p as i64 };
+            usize::try_from(idx)
+                .ok()
+                .and_then(|i| candidates.get(i).copied())
+        })
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Resolve a wall-clock time in `tz`. Ambiguous times take the first occurrence and times that
+/// fall into a DST gap are shifted forward by the length of the gap, as RFC 5545 requires.
+pub(crate) fn resolve_local(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    if let Some(dt) = tz.from_local_datetime(&local).earliest() {
+        return dt;
+    }
+    let before = tz.offset_from_utc_datetime(&(local - Duration::days(1)));
+    tz.from_utc_datetime(&(local - Duration::seconds(before.fix().local_minus_utc() as i64)))
+}
+
+fn week_one_start(year: i32, week_start: Weekday) -> NaiveDate {
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or_default();
+    let offset = days_between(week_start, jan1.weekday());
+    if offset <= 3 {
+        jan1 - Duration::days(offset)
+    } else {
+        jan1 + Duration::days(7 - offset)
+    }
+}
+
+/// The week-numbering year of the day, its week number and the number of weeks in that year.
+fn week_number(d: NaiveDate, week_start: Weekday) -> (i32, i64, i64) {
+    let mut year = d.year();
+    if d >= week_one_start(year + 1, week_start) {
+        year += 1;
+    } else if d < week_one_start(year, week_start) {
+        year -= 1;
+    }
+    let first = week_one_start(year, week_start);
+    let weeks = (week_one_start(year + 1, week_start) - first).num_days() / 7;
+    (year, (d - first).num_days() / 7 + 1, weeks)
+}
+
+fn days_between(from: Weekday, to: Weekday) -> i64 {
+    (to.num_days_from_monday() as i64 - from.num_days_from_monday() as i64).rem_euclid(7)
+}
+
+fn days_in_year(year: i32) -> i64 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366
+    } else {
+        365
+    }
+}
+
+fn days_in_month(d: NaiveDate) -> i64 {
+    let (y, m) = if d.month() == 12 {
+        (d.year() + 1, 1)
+    } else {
+        (d.year(), d.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(y, m, 1)
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day() as i64)
+}
+
+fn weekday_str(wd: Weekday) -> &'static str {
+    match wd {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_weekday(s: &str) -> ClientResult<Weekday> {
+    Ok(match s {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(invalid(format!("invalid weekday `{}`", s))),
+    })
+}
+
+fn invalid(msg: String) -> ClientError {
+    ClientError::InvalidRecurrence(msg)
+}
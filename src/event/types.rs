@@ -2,36 +2,22 @@ use serde::{Deserialize, Serialize};
 
 use super::{AdditionalProperties, DefaultReminder};
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy)]
-#[serde(rename_all = "camelCase")]
-pub enum SendUpdates {
-    #[default]
-    All,
-    ExternalOnly,
-    None,
-}
-impl SendUpdates {
-    pub fn to_str(&self) -> &'static str {
-        match self {
-            Self::All => "all",
-            Self::ExternalOnly => "externalOnly",
-            Self::None => "none",
-        }
-    }
-}
-impl std::fmt::Display for SendUpdates {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.to_str())
+api_enum! {
+    pub enum SendUpdates {
+        #[default]
+        All => "all",
+        ExternalOnly => "externalOnly",
+        None => "none",
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventOfficeLocationType {
-    #[default]
-    HomeOffice,
-    OfficeLocation,
-    CustomLocation,
+api_enum! {
+    pub enum EventOfficeLocationType {
+        #[default]
+        HomeOffice => "homeOffice",
+        OfficeLocation => "officeLocation",
+        CustomLocation => "customLocation",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -47,7 +33,6 @@ pub struct EventOfficeLocation {
     pub floor_section_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
-    pub typ: EventOfficeLocationType,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -60,39 +45,113 @@ pub struct EventCustomLocation {
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct EventWorkingLocation {
+    #[serde(rename = "type")]
+    pub typ: EventOfficeLocationType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub custom_location: Option<EventCustomLocation>,
+    /// Set to an empty object when working from home.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub home_office: Option<String>,
+    pub home_office: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub office_location: Option<EventOfficeLocation>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventVisibility {
-    #[default]
-    Default,
-    Public,
-    Private,
-    Confidential,
+impl EventWorkingLocation {
+    /// Working from home.
+    pub fn home() -> Self {
+        Self {
+            typ: EventOfficeLocationType::HomeOffice,
+            home_office: Some(serde_json::Value::Object(Default::default())),
+            ..Default::default()
+        }
+    }
+
+    /// Working from an office, optionally labelled.
+    pub fn office(label: Option<String>) -> Self {
+        Self {
+            typ: EventOfficeLocationType::OfficeLocation,
+            office_location: Some(EventOfficeLocation {
+                label,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Working from somewhere else.
+    pub fn custom(label: impl ToString) -> Self {
+        Self {
+            typ: EventOfficeLocationType::CustomLocation,
+            custom_location: Some(EventCustomLocation {
+                label: Some(label.to_string()),
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+api_enum! {
+    pub enum EventAutoDeclineMode {
+        #[default]
+        DeclineNone => "declineNone",
+        DeclineAllConflictingInvitations => "declineAllConflictingInvitations",
+        DeclineOnlyNewConflictingInvitations => "declineOnlyNewConflictingInvitations",
+    }
+}
+
+api_enum! {
+    pub enum EventChatStatus {
+        #[default]
+        Available => "available",
+        DoNotDisturb => "doNotDisturb",
+    }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-pub enum EventTransparency {
-    #[default]
-    Opaque,
-    Transparent,
+pub struct EventOutOfOfficeProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_decline_mode: Option<EventAutoDeclineMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_message: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-pub enum EventStatus {
-    Confirmed,
-    #[default]
-    Tentative,
-    Cancelled,
+pub struct EventFocusTimeProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_decline_mode: Option<EventAutoDeclineMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decline_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_status: Option<EventChatStatus>,
+}
+
+api_enum! {
+    pub enum EventVisibility {
+        #[default]
+        Default => "default",
+        Public => "public",
+        Private => "private",
+        Confidential => "confidential",
+    }
+}
+
+api_enum! {
+    pub enum EventTransparency {
+        #[default]
+        Opaque => "opaque",
+        Transparent => "transparent",
+    }
+}
+
+api_enum! {
+    pub enum EventStatus {
+        Confirmed => "confirmed",
+        #[default]
+        Tentative => "tentative",
+        Cancelled => "cancelled",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -122,12 +181,12 @@ pub struct EventOrganizer {
     pub appears_as_self: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventGadgetDisplay {
-    #[default]
-    Icon,
-    Chip,
+api_enum! {
+    pub enum EventGadgetDisplay {
+        #[default]
+        Icon => "icon",
+        Chip => "chip",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -147,14 +206,38 @@ pub struct EventExtendedProperties {
     pub shared: Option<AdditionalProperties>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+api_enum! {
+    pub enum EventType {
+        #[default]
+        Default => "default",
+        OutOfOffice => "outOfOffice",
+        FocusTime => "focusTime",
+        WorkingLocation => "workingLocation",
+        Birthday => "birthday",
+        FromGmail => "fromGmail",
+    }
+}
+
+api_enum! {
+    pub enum EventBirthdayType {
+        #[default]
+        Birthday => "birthday",
+        Anniversary => "anniversary",
+        Custom => "custom",
+        Other => "other",
+        SelfBirthday => "self",
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-pub enum EventType {
-    #[default]
-    Default,
-    OutOfOffice,
-    FocusTime,
-    WorkingLocation,
+pub struct EventBirthdayProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact: Option<String>,
+    #[serde(rename = "type")]
+    pub typ: EventBirthdayType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_type_name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -168,6 +251,33 @@ pub struct EventCalendarDate {
     pub time_zone: Option<String>,
 }
 
+impl EventCalendarDate {
+    /// A timed date, keeping the offset of `dt`.
+    pub fn from_datetime<Tz: chrono::TimeZone>(dt: &chrono::DateTime<Tz>) -> Self
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        Self {
+            date_time: Some(dt.to_rfc3339()),
+            ..Default::default()
+        }
+    }
+
+    /// An all-day date.
+    pub fn from_date(date: chrono::NaiveDate) -> Self {
+        Self {
+            date: Some(date.format("%Y-%m-%d").to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Set the IANA time zone the date is expressed in, e.g. `Europe/Zurich`.
+    pub fn with_time_zone(mut self, time_zone: impl ToString) -> Self {
+        self.time_zone = Some(time_zone.to_string());
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct EventConferenceData {
@@ -177,7 +287,8 @@ pub struct EventConferenceData {
     pub conference_solution: Option<EventConferenceSolution>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub create_request: Option<EventCreateConferenceRequest>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    /// Empty while a requested conference is still being created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub entry_points: Vec<EventConferenceEntryPoint>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
@@ -202,52 +313,99 @@ pub struct EventCreator {
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct EventConferenceEntryPoint {
-    entry_point_type: EventConferenceEntryPointType,
+    pub entry_point_type: EventConferenceEntryPointType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    meeting_code: Option<String>,
+    pub meeting_code: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    passcode: Option<String>,
+    pub passcode: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    password: Option<String>,
+    pub password: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pin: Option<String>,
+    pub pin: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    uri: Option<String>,
+    pub uri: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventConferenceEntryPointType {
-    #[default]
-    Video,
-    Phone,
-    SIP,
-    More,
+api_enum! {
+    pub enum EventConferenceEntryPointType {
+        #[default]
+        Video => "video",
+        Phone => "phone",
+        SIP => "sip",
+        More => "more",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct EventCreateConferenceRequest {
-    conference_solution_key: EventConferenceSolutionKey,
-    request_id: String,
-    status: EventConferenceStatus,
+    pub conference_solution_key: EventConferenceSolutionKey,
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<EventConferenceStatus>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct EventConferenceStatus {
-    status_code: EventConferenceStatusCode,
+    pub status_code: EventConferenceStatusCode,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventConferenceStatusCode {
-    #[default]
-    Pending,
-    Success,
-    Failure,
+impl EventConferenceData {
+    /// Conference data asking Google to create a new conference of the given solution type.
+    /// Requires `conferenceDataVersion=1` on the request, see `Event::request_conference`.
+    pub fn create(typ: EventConferenceSolutionKeyType) -> Self {
+        Self {
+            create_request: Some(EventCreateConferenceRequest::new(typ)),
+            ..Default::default()
+        }
+    }
+
+    /// Conference data asking Google to create a new Google Meet conference.
+    pub fn hangouts_meet() -> Self {
+        Self::create(EventConferenceSolutionKeyType::HangoutsMeet)
+    }
+
+    /// The status of a pending conference creation request.
+    pub fn status(&self) -> Option<&EventConferenceStatusCode> {
+        self.create_request
+            .as_ref()
+            .and_then(|r| r.status.as_ref())
+            .map(|s| &s.status_code)
+    }
+}
+
+impl EventCreateConferenceRequest {
+    /// A creation request with a unique request ID, made of the current time and a counter so
+    /// requests created within the same instant still differ.
+    pub fn new(typ: EventConferenceSolutionKeyType) -> Self {
+        use std::{
+            sync::atomic::{AtomicU64, Ordering},
+            time::{SystemTime, UNIX_EPOCH},
+        };
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Self {
+            conference_solution_key: EventConferenceSolutionKey { typ, name: None },
+            request_id: format!("{:x}-{:x}", nanos, COUNTER.fetch_add(1, Ordering::Relaxed)),
+            status: None,
+        }
+    }
+}
+
+api_enum! {
+    pub enum EventConferenceStatusCode {
+        #[default]
+        Pending => "pending",
+        Success => "success",
+        Failure => "failure",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -266,14 +424,14 @@ pub struct EventConferenceSolutionKey {
     pub name: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventConferenceSolutionKeyType {
-    EventHangout,
-    EventNamedHangout,
-    #[default]
-    HangoutsMeet,
-    AddOn,
+api_enum! {
+    pub enum EventConferenceSolutionKeyType {
+        EventHangout => "eventHangout",
+        EventNamedHangout => "eventNamedHangout",
+        #[default]
+        HangoutsMeet => "hangoutsMeet",
+        AddOn => "addOn",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -300,22 +458,26 @@ pub struct EventAttendees {
     pub appears_as_self: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum EventResponseStatus {
-    #[default]
-    NeedsAction,
-    Declined,
-    Tentative,
-    Accepted,
+api_enum! {
+    pub enum EventResponseStatus {
+        #[default]
+        NeedsAction => "needsAction",
+        Declined => "declined",
+        Tentative => "tentative",
+        Accepted => "accepted",
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct EventAttachment {
-    pub file_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<String>,
     pub file_url: String,
-    pub icon_link: String,
-    pub mime_type: String,
-    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
\ No newline at end of file
@@ -21,13 +21,37 @@
 //! }
 //! ```
 
+/// Forward-compatible enums for the string enums of the Google API.
+#[macro_use]
+mod macros;
+
 /// Core client, used to construct other clients.
 mod client;
 pub use client::*;
 
+/// Clients of many users, looked up by user ID or email.
+mod account;
+pub use account::*;
+
 pub mod oauth;
 pub use oauth::*;
 
+/// Token persistence, saving and restoring tokens across restarts.
+pub mod token_store;
+pub use token_store::*;
+
+/// Token storage encrypted at rest.
+pub mod encrypted_store;
+pub use encrypted_store::*;
+
+/// Service accounts, authenticating without a user through signed JWT assertions.
+pub mod service_account;
+pub use service_account::*;
+
+/// OAuth scopes and checking them before a request is sent.
+mod scope;
+pub use scope::{CalendarScope, CalendarScopes};
+
 /// Calendar types.
 mod calendar;
 pub use calendar::*;
@@ -45,4 +69,4 @@ mod sendable;
 pub use sendable::*;
 
 mod error;
-pub use error::{ClientError, ClientResult};
+pub use error::{ClientError, ClientResult, EventValidationError, ParseAccessRoleError};
@@ -1,25 +1,129 @@
-use std::time::{Duration, SystemTime};
+use std::{
+    borrow::Cow,
+    time::{Duration, SystemTime},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use futures_util::{stream::FuturesUnordered, StreamExt};
 use oauth2::{
-    basic::{BasicClient, BasicTokenType},
-    reqwest, AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EmptyExtraTokenFields,
-    PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken, RevocationUrl, Scope,
-    StandardTokenResponse, TokenResponse, TokenUrl,
+    basic::{
+        BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse,
+        BasicTokenType,
+    },
+    reqwest, AuthUrl, AuthorizationCode, Client, ClientId, ClientSecret, CsrfToken,
+    DeviceAuthorizationUrl, ExtraTokenFields, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl,
+    RefreshToken, RevocationUrl, Scope, StandardDeviceAuthorizationResponse,
+    StandardRevocableToken, StandardTokenResponse, TokenResponse, TokenUrl,
 };
 use serde::{Deserialize, Serialize};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::TcpListener,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
     sync::Mutex,
 };
 
+use super::{CalendarScope, CalendarScopes, TokenStore};
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct OToken {
     pub access: String,
     pub refresh: Option<String>,
+    /// The scopes Google granted, `None` when the token response didn't say. Requests needing a
+    /// scope that wasn't granted fail with `ClientError::InsufficientScope` before being sent.
+    #[serde(default)]
+    pub scopes: Option<CalendarScopes>,
+    /// When the access token stops being accepted, a minute early to allow for clock skew. `None`
+    /// when unknown, the token is then never considered expired.
+    pub expires_at: Option<SystemTime>,
+    /// How to present the access token, `bearer` for Google.
+    #[serde(default)]
+    pub token_type: Option<String>,
+    /// The OpenID Connect ID token, a JWT about the user, when the `openid` scope was granted.
+    #[serde(default)]
+    pub id_token: Option<String>,
+}
+
+/// The fields Google adds to token responses.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+struct GoogleTokenFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id_token: Option<String>,
+}
+
+impl ExtraTokenFields for GoogleTokenFields {}
+
+type GoogleTokenResponse = StandardTokenResponse<GoogleTokenFields, BasicTokenType>;
+
+type GoogleClient = Client<
+    BasicErrorResponse,
+    GoogleTokenResponse,
+    BasicTokenType,
+    BasicTokenIntrospectionResponse,
+    StandardRevocableToken,
+    BasicRevocationErrorResponse,
+>;
+
+/// TokenInfo is what Google knows about an access token, see `OAuth::token_info`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    /// The ID of the OAuth client the token was issued to.
+    pub audience: String,
+    /// The user's Google ID, when one of the email, profile or openid scopes was granted.
+    pub user_id: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub scopes: CalendarScopes,
+    /// How long until the token expires.
+    pub expires_in: Option<Duration>,
+    /// `offline` when a refresh token was issued along with it.
+    pub access_type: Option<String>,
+}
+
+/// The tokeninfo response, which has its numbers and booleans as strings.
+#[derive(Deserialize)]
+struct RawTokenInfo {
+    #[serde(default)]
+    aud: String,
+    sub: Option<String>,
+    email: Option<String>,
+    email_verified: Option<String>,
+    #[serde(default)]
+    scope: String,
+    expires_in: Option<String>,
+    access_type: Option<String>,
+}
+
+impl From<RawTokenInfo> for TokenInfo {
+    fn from(raw: RawTokenInfo) -> Self {
+        Self {
+            audience: raw.aud,
+            user_id: raw.sub,
+            email: raw.email,
+            email_verified: raw.email_verified.as_deref() == Some("true"),
+            scopes: CalendarScopes::parse(&raw.scope),
+            expires_in: raw
+                .expires_in
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_secs),
+            access_type: raw.access_type,
+        }
+    }
+}
 
-    expires_at: Option<SystemTime>,
+/// The error object of Google's OAuth endpoints.
+#[derive(Deserialize)]
+struct EndpointError {
+    error: String,
+    error_description: Option<String>,
+}
+
+impl std::fmt::Display for EndpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error_description {
+            Some(description) => write!(f, "{}: {}", self.error, description),
+            None => write!(f, "{}", self.error),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -29,10 +133,70 @@ pub struct OAuthRequest {
     pub scope: String,
 }
 
+/// DeviceAuthorization is a pending device flow, see `OAuth::device_code`.
+#[derive(Debug, Clone)]
+pub struct DeviceAuthorization(StandardDeviceAuthorizationResponse);
+
+impl DeviceAuthorization {
+    /// The code the user enters at the verification URL.
+    pub fn user_code(&self) -> &str {
+        self.0.user_code().secret()
+    }
+
+    /// Where the user enters the code, e.g. `https://www.google.com/device`.
+    pub fn verification_url(&self) -> &str {
+        self.0.verification_uri()
+    }
+
+    /// A URL with the code filled in, when the server provides one.
+    pub fn verification_url_complete(&self) -> Option<&str> {
+        self.0
+            .verification_uri_complete()
+            .map(|url| url.secret().as_str())
+    }
+
+    /// How long the user has to enter the code.
+    pub fn expires_in(&self) -> Duration {
+        self.0.expires_in()
+    }
+
+    /// How long to wait between polls initially.
+    pub fn interval(&self) -> Duration {
+        self.0.interval()
+    }
+}
+
 #[derive(Debug)]
 pub struct OAuth {
-    client: BasicClient,
+    client: GoogleClient,
+    http: ::reqwest::Client,
+    tokeninfo_url: url::Url,
     pkce_code_verifier: Mutex<Option<PkceCodeVerifier>>,
+    csrf_state: Mutex<Option<CsrfToken>>,
+    scopes: CalendarScopes,
+}
+
+/// OAuthEndpoints are the URLs of the OAuth server, Google's by default. Point them elsewhere to
+/// test against a local server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthEndpoints {
+    pub auth_url: String,
+    pub token_url: String,
+    pub device_authorization_url: String,
+    pub revocation_url: String,
+    pub tokeninfo_url: String,
+}
+
+impl Default for OAuthEndpoints {
+    fn default() -> Self {
+        Self {
+            auth_url: "https://accounts.google.com/o/oauth2/v2/auth".to_string(),
+            token_url: "https://www.googleapis.com/oauth2/v3/token".to_string(),
+            device_authorization_url: "https://oauth2.googleapis.com/device/code".to_string(),
+            revocation_url: "https://oauth2.googleapis.com/revoke".to_string(),
+            tokeninfo_url: "https://oauth2.googleapis.com/tokeninfo".to_string(),
+        }
+    }
 }
 
 impl OAuth {
@@ -41,27 +205,71 @@ impl OAuth {
         client_secret: impl ToString,
         redir_url: impl ToString,
     ) -> Self {
+        Self::with_endpoints(
+            client_id,
+            client_secret,
+            redir_url,
+            OAuthEndpoints::default(),
+        )
+        .expect("Invalid redirect URL")
+    }
+
+    /// Like `new`, talking to the given endpoints instead of Google's.
+    pub fn with_endpoints(
+        client_id: impl ToString,
+        client_secret: impl ToString,
+        redir_url: impl ToString,
+        endpoints: OAuthEndpoints,
+    ) -> Result<Self> {
         // Set up the config for the Google OAuth2 process.
-        Self {
-            client: BasicClient::new(
+        Ok(Self {
+            client: GoogleClient::new(
                 ClientId::new(client_id.to_string()),
                 Some(ClientSecret::new(client_secret.to_string())),
-                AuthUrl::new("https://accounts.google.com/o/oauth2/v2/auth".to_string())
-                    .expect("Invalid authorization endpoint URL"),
-                Some(
-                    TokenUrl::new("https://www.googleapis.com/oauth2/v3/token".to_string())
-                        .expect("Invalid token endpoint URL"),
-                ),
+                AuthUrl::new(endpoints.auth_url).context("Invalid authorization endpoint URL")?,
+                Some(TokenUrl::new(endpoints.token_url).context("Invalid token endpoint URL")?),
             )
             .set_redirect_uri(
-                RedirectUrl::new(redir_url.to_string()).expect("Invalid redirect URL"),
+                RedirectUrl::new(redir_url.to_string()).context("Invalid redirect URL")?,
             )
             .set_revocation_uri(
-                RevocationUrl::new("https://oauth2.googleapis.com/revoke".to_string())
-                    .expect("Invalid revocation endpoint URL"),
+                RevocationUrl::new(endpoints.revocation_url)
+                    .context("Invalid revocation endpoint URL")?,
+            )
+            .set_device_authorization_url(
+                DeviceAuthorizationUrl::new(endpoints.device_authorization_url)
+                    .context("Invalid device authorization endpoint URL")?,
             ),
+            http: ::reqwest::Client::new(),
+            tokeninfo_url: url::Url::parse(&endpoints.tokeninfo_url)
+                .context("Invalid tokeninfo endpoint URL")?,
             pkce_code_verifier: Mutex::new(None),
-        }
+            csrf_state: Mutex::new(None),
+            scopes: CalendarScopes::from([
+                CalendarScope::Calendar,
+                CalendarScope::UserInfoProfile,
+                CalendarScope::UserInfoEmail,
+            ]),
+        })
+    }
+
+    /// Ask for these scopes instead of full calendar access plus the user's email and profile,
+    /// e.g. only `CalendarScope::CalendarReadonly` for a read-only dashboard.
+    pub fn with_scopes(mut self, scopes: impl Into<CalendarScopes>) -> Self {
+        self.scopes = scopes.into();
+        self
+    }
+
+    /// Send revocations and token info lookups through this client, sharing its connection pool with e.g. the one given
+    /// to `GCalClientBuilder::http_client`.
+    pub fn with_http_client(mut self, http: ::reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// The scopes requested by `auth_url`.
+    pub fn scopes(&self) -> &CalendarScopes {
+        &self.scopes
     }
 
     pub async fn exhange_refresh(&self, ref_token: impl ToString) -> Result<OToken> {
@@ -74,119 +282,484 @@ impl OAuth {
     }
 
     pub async fn auth_url(&self) -> String {
-        // Google supports Proof Key for Code Exchange (PKCE - https://oauth.net/2/pkce/).
-        // Create a PKCE code verifier and SHA-256 encode it as a code challenge.
-        let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
-        self.pkce_code_verifier
-            .lock()
-            .await
-            .get_or_insert(pkce_code_verifier);
-
-        // Generate the authorization URL to which we'll redirect the user.
-        let (authorize_url, _csrf_state) = self
-            .client
-            .authorize_url(CsrfToken::new_random)
-            // This example is requesting access to the "calendar" features and the user's profile.
-            .add_scope(Scope::new(
-                "https://www.googleapis.com/auth/calendar".to_string(),
-            ))
-            .add_scope(Scope::new(
-                "https://www.googleapis.com/auth/userinfo.profile".to_string(),
-            ))
-            .add_scope(Scope::new(
-                "https://www.googleapis.com/auth/userinfo.email".to_string(),
-            ))
-            .set_pkce_challenge(pkce_code_challenge)
-            .url();
-        authorize_url.to_string()
+        let (authorize_url, csrf_state, pkce_code_verifier) = self.authorize(None);
+        *self.pkce_code_verifier.lock().await = Some(pkce_code_verifier);
+        *self.csrf_state.lock().await = Some(csrf_state);
+        authorize_url
     }
 
+    /// Exchange the code of a redirect to the URL of `auth_url`. Fails when its state is not the
+    /// one `auth_url` sent.
     pub async fn auth(&self, request: OAuthRequest) -> Result<(String, OToken)> {
-        let _scope = request.scope;
+        if let Some(state) = self.csrf_state.lock().await.take() {
+            if state.secret() != &request.state {
+                bail!("Authorization redirect has an invalid state");
+            }
+        }
 
         // Exchange the code with a token.
-        Ok((
-            CsrfToken::new(request.state).secret().clone(),
-            self.client
-                .exchange_code(AuthorizationCode::new(request.code))
-                .set_pkce_verifier(
-                    self.pkce_code_verifier
-                        .lock()
-                        .await
-                        .take()
-                        .context("PKCE code verifier should exist at this point")?,
-                )
-                .request_async(reqwest::async_http_client)
-                .await?
-                .into(),
-        ))
+        let verifier = self
+            .pkce_code_verifier
+            .lock()
+            .await
+            .take()
+            .context("PKCE code verifier should exist at this point")?;
+        let token = self.exchange(request.code, verifier, None).await?;
+        Ok((request.state, token.or_scopes(Some(&request.scope))))
     }
 
     pub async fn refresh(&self, token: &mut OToken) -> Result<()> {
         if token.is_expired() {
-            let t = self
-                .exhange_refresh(token.refresh.take().context("Refresh token should exist")?)
-                .await?;
-            token.take_over(t);
+            self.renew(token).await?;
         }
         Ok(())
     }
 
-    pub async fn naive(&mut self) -> Result<OToken> {
-        async fn listener() -> Option<OAuthRequest> {
-            fn query(url: &url::Url, key: &str) -> Option<String> {
-                url.query_pairs()
-                    .find(|(k, _)| k == key)
-                    .map(|(_, state)| state.into_owned())
+    /// Exchange the refresh token for a new access token now, whether or not it expired. The
+    /// refresh token is kept when the exchange fails or the response has no new one.
+    pub async fn renew(&self, token: &mut OToken) -> Result<()> {
+        let refresh = token
+            .refresh
+            .clone()
+            .context("Refresh token should exist")?;
+        token.take_over(self.exhange_refresh(refresh).await?);
+        Ok(())
+    }
+
+    /// Revoke the grant behind `token`, e.g. for a "disconnect Google account" button. The refresh
+    /// token is revoked when there is one, which also revokes the access tokens issued for it.
+    /// Tokens that are already invalid count as revoked.
+    pub async fn revoke(&self, token: &OToken) -> Result<()> {
+        let (secret, hint) = match &token.refresh {
+            Some(refresh) => (refresh.as_str(), "refresh_token"),
+            None => (token.access.as_str(), "access_token"),
+        };
+        if secret.is_empty() {
+            return Ok(());
+        }
+
+        let url = self
+            .client
+            .revocation_url()
+            .context("Revocation URL should exist")?;
+        let resp = self
+            .http
+            .post(url.url().as_str())
+            .form(&[("token", secret), ("token_type_hint", hint)])
+            .send()
+            .await?;
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        let status = resp.status();
+        match resp.json::<EndpointError>().await {
+            Ok(e) if e.error == "invalid_token" => Ok(()),
+            Ok(e) => bail!("Revocation failed: {}", e),
+            Err(_) => bail!("Revocation failed with {}", status),
+        }
+    }
+
+    /// Ask Google about `token`'s access token: whom and which client it was issued to, the
+    /// scopes it grants and how long it stays valid. Fails when it is invalid or expired. The
+    /// token is sent in the body so that it doesn't end up in server or proxy logs.
+    pub async fn token_info(&self, token: &OToken) -> Result<TokenInfo> {
+        let resp = self
+            .http
+            .post(self.tokeninfo_url.clone())
+            .form(&[("access_token", &token.access)])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            match resp.json::<EndpointError>().await {
+                Ok(e) => bail!("Token info request failed: {}", e),
+                Err(_) => bail!("Token info request failed with {}", status),
             }
-            // // A very naive implementation of the redirect server.
-            let listener = TcpListener::bind("127.0.0.1:5000").await.unwrap();
-            loop {
-                if let Ok((mut stream, _)) = listener.accept().await {
-                    let mut reader = BufReader::new(&mut stream);
-                    let mut request_line = String::new();
-                    reader.read_line(&mut request_line).await.unwrap();
-
-                    let url = url::Url::parse(
-                        &("http://localhost".to_string()
-                            + request_line.split_whitespace().nth(1)?),
-                    )
-                    .ok()?;
-
-                    let auth = OAuthRequest {
-                        code: query(&url, "code")?,
-                        state: query(&url, "state")?,
-                        scope: query(&url, "scope")?,
-                    };
-
-                    let message = "Go back to your application!";
-                    stream
-                        .write_all(
-                            format!(
-                                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
-                                message.len(),
-                                message
-                            )
-                            .as_bytes(),
-                        )
-                        .await
-                        .ok()?;
-
-                    // The server will terminate itself after collecting the first code.
-                    break (Some(auth));
-                }
+        }
+        Ok(resp.json::<RawTokenInfo>().await?.into())
+    }
+
+    /// Revoke the token saved in `store` and clear the store. Like `GCalClient::sign_out`, the
+    /// store is cleared even when revoking fails, e.g. while Google is unavailable. The revoke
+    /// error is still returned; load the token beforehand to retry with `revoke`.
+    pub async fn sign_out(&self, store: &dyn TokenStore) -> Result<()> {
+        let revoked = match store.load()? {
+            Some(token) => self.revoke(&token).await,
+            None => Ok(()),
+        };
+        store.clear()?;
+        revoked
+    }
+
+    /// Start the device flow for machines without a browser (RFC 8628). Show the user code and
+    /// verification URL to the user, then wait for them with `device_token`. Requires an OAuth
+    /// client of the "TVs and Limited Input devices" type.
+    pub async fn device_code(&self) -> Result<DeviceAuthorization> {
+        let response: StandardDeviceAuthorizationResponse = self
+            .client
+            .exchange_device_code()?
+            .add_scopes(self.scopes.iter().map(|s| Scope::new(s.to_string())))
+            .request_async(reqwest::async_http_client)
+            .await?;
+        Ok(DeviceAuthorization(response))
+    }
+
+    /// Poll the token endpoint until the user approved the device, waiting the interval Google
+    /// asks for and longer whenever it answers `slow_down`. Fails when the user denied access or
+    /// the code expired.
+    pub async fn device_token(&self, authorization: &DeviceAuthorization) -> Result<OToken> {
+        Ok(self
+            .client
+            .exchange_device_access_token(&authorization.0)
+            .request_async(reqwest::async_http_client, tokio::time::sleep, None)
+            .await?
+            .into())
+    }
+
+    /// Run the whole device flow, printing the code to enter and where to stdout.
+    pub async fn device(&self) -> Result<OToken> {
+        let authorization = self.device_code().await?;
+        println!(
+            "🔗 Open {} and enter the code {}",
+            authorization.verification_url(),
+            authorization.user_code()
+        );
+
+        let token = self.device_token(&authorization).await?;
+        println!("[INFO] Successfully retrieved access token.");
+        Ok(token)
+    }
+
+    /// Run the installed-app flow with default options through a redirect server listening on
+    /// the redirect URL given to `new`, e.g. `http://localhost:5000/auth`. See
+    /// `loopback_on_redirect_url`.
+    pub async fn naive(&self) -> Result<OToken> {
+        self.loopback_on_redirect_url(LoopbackOptions::default())
+            .await?
+            .run()
+            .await
+    }
+
+    /// Bind a redirect server on an ephemeral loopback port for the installed-app flow. The
+    /// redirect URL given to `new` is replaced by `http://127.0.0.1:<port>/`, which Google accepts
+    /// for "Desktop app" OAuth clients.
+    pub async fn loopback(&self, options: LoopbackOptions) -> Result<Loopback<'_>> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Failed to bind the redirect server")?;
+        let redirect = RedirectUrl::new(format!("http://{}/", listener.local_addr()?))?;
+        Ok(self.bound_loopback(listener, redirect, options))
+    }
+
+    /// Like `loopback`, but bind the host and port of the redirect URL given to `new` and keep
+    /// that URL, for OAuth clients that only accept their registered redirect URL.
+    pub async fn loopback_on_redirect_url(&self, options: LoopbackOptions) -> Result<Loopback<'_>> {
+        let redirect = self
+            .client
+            .redirect_url()
+            .context("No redirect URL configured")?
+            .clone();
+        let url = redirect.url();
+        let host = url
+            .host_str()
+            .context("Redirect URL has no host")?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let port = url
+            .port_or_known_default()
+            .context("Redirect URL has no port")?;
+        let listener = TcpListener::bind((host, port))
+            .await
+            .context("Failed to bind the redirect server")?;
+        Ok(self.bound_loopback(listener, redirect, options))
+    }
+
+    fn bound_loopback(
+        &self,
+        listener: TcpListener,
+        redirect: RedirectUrl,
+        options: LoopbackOptions,
+    ) -> Loopback<'_> {
+        let (url, state, verifier) = self.authorize(Some(&redirect));
+        Loopback {
+            oauth: self,
+            listener,
+            redirect,
+            url,
+            state,
+            verifier,
+            options,
+        }
+    }
+
+    fn authorize(&self, redirect: Option<&RedirectUrl>) -> (String, CsrfToken, PkceCodeVerifier) {
+        // Google supports Proof Key for Code Exchange (PKCE - https://oauth.net/2/pkce/).
+        // Create a PKCE code verifier and SHA-256 encode it as a code challenge.
+        let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        // Generate the authorization URL to which we'll redirect the user.
+        let mut request = self
+            .client
+            .authorize_url(CsrfToken::new_random)
+            .add_scopes(self.scopes.iter().map(|s| Scope::new(s.to_string())))
+            .set_pkce_challenge(pkce_code_challenge);
+        if let Some(redirect) = redirect {
+            request = request.set_redirect_uri(Cow::Borrowed(redirect));
+        }
+        let (authorize_url, csrf_state) = request.url();
+        (authorize_url.to_string(), csrf_state, pkce_code_verifier)
+    }
+
+    async fn exchange(
+        &self,
+        code: String,
+        verifier: PkceCodeVerifier,
+        redirect: Option<&RedirectUrl>,
+    ) -> Result<OToken> {
+        let mut request = self
+            .client
+            .exchange_code(AuthorizationCode::new(code))
+            .set_pkce_verifier(verifier);
+        if let Some(redirect) = redirect {
+            request = request.set_redirect_uri(Cow::Borrowed(redirect));
+        }
+        Ok(request
+            .request_async(reqwest::async_http_client)
+            .await?
+            .into())
+    }
+}
+
+/// LoopbackOptions configure the redirect server of `OAuth::loopback`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopbackOptions {
+    /// Served to the browser once the authorization code was received.
+    pub success_html: String,
+    /// Served to the browser when access was denied or the redirect was invalid.
+    pub failure_html: String,
+    /// How long to wait for the user to finish, five minutes by default.
+    pub timeout: Duration,
+    /// Open the authorization URL in the default browser in `Loopback::run`.
+    pub open_browser: bool,
+}
+
+impl Default for LoopbackOptions {
+    fn default() -> Self {
+        Self {
+            success_html:
+                "<html><body>Authorization complete, go back to your application!</body></html>"
+                    .to_string(),
+            failure_html:
+                "<html><body>Authorization failed, go back to your application.</body></html>"
+                    .to_string(),
+            timeout: Duration::from_secs(300),
+            open_browser: false,
+        }
+    }
+}
+
+/// Loopback is a bound redirect server waiting for the browser to come back with a code, see
+/// `OAuth::loopback`.
+#[derive(Debug)]
+pub struct Loopback<'a> {
+    oauth: &'a OAuth,
+    listener: TcpListener,
+    redirect: RedirectUrl,
+    url: String,
+    state: CsrfToken,
+    verifier: PkceCodeVerifier,
+    options: LoopbackOptions,
+}
+
+impl Loopback<'_> {
+    /// The URL the user has to open to grant access.
+    pub fn auth_url(&self) -> &str {
+        &self.url
+    }
+
+    /// The redirect URL pointing at this server.
+    pub fn redirect_url(&self) -> &str {
+        self.redirect.as_str()
+    }
+
+    /// Print the authorization URL, open it when configured to, and wait for the redirect.
+    pub async fn run(self) -> Result<OToken> {
+        println!("🔗 Open this URL: {}", self.url);
+        if self.options.open_browser {
+            if let Err(e) = open_browser(&self.url) {
+                println!("[WARN] Failed to open the browser: {}", e);
             }
         }
-        println!("🔗 Open this URL: {}", self.auth_url().await);
 
-        let auth = listener().await.context("Failed to get auth response.")?;
-        let (_, token) = self.auth(auth).await?;
+        let token = self.wait().await?;
         println!("[INFO] Successfully retrieved access token.");
         Ok(token)
     }
+
+    /// Wait for the redirect and exchange its code. Fails when the user denied access, the state
+    /// doesn't match the one sent, or nothing arrived within the timeout.
+    pub async fn wait(self) -> Result<OToken> {
+        let (code, scope) = tokio::time::timeout(self.options.timeout, self.receive())
+            .await
+            .context("Timed out waiting for the authorization redirect")??;
+        let token = self
+            .oauth
+            .exchange(code, self.verifier, Some(&self.redirect))
+            .await?;
+        Ok(token.or_scopes(scope.as_deref()))
+    }
+
+    /// The code and granted scopes of the redirect.
+    async fn receive(&self) -> Result<(String, Option<String>)> {
+        fn query(url: &url::Url, key: &str) -> Option<String> {
+            url.query_pairs()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.into_owned())
+        }
+
+        // Browsers open speculative connections that may never send a request, so requests are
+        // read concurrently and an idle connection can't hold up the redirect.
+        let mut requests = FuturesUnordered::new();
+        loop {
+            let (mut stream, url) = tokio::select! {
+                accepted = self.listener.accept() => {
+                    requests.push(read_request(accepted?.0));
+                    continue;
+                }
+                Some(request) = requests.next() => request,
+            };
+            let Some(url) = url else {
+                continue;
+            };
+
+            let code = query(&url, "code");
+            let error = query(&url, "error");
+            if code.is_none() && error.is_none() {
+                // Not the redirect, e.g. the browser asking for a favicon.
+                respond(&mut stream, "404 Not Found", "").await;
+                continue;
+            }
+
+            let result = if query(&url, "state").as_deref() != Some(self.state.secret()) {
+                Err(anyhow!("Authorization redirect has an invalid state"))
+            } else if let Some(error) = error {
+                Err(match error.as_str() {
+                    "access_denied" => anyhow!("Authorization was denied by the user"),
+                    _ => match query(&url, "error_description") {
+                        Some(description) => {
+                            anyhow!("Authorization failed: {}: {}", error, description)
+                        }
+                        None => anyhow!("Authorization failed: {}", error),
+                    },
+                })
+            } else {
+                code.context("Authorization redirect has no code")
+                    .map(|code| (code, query(&url, "scope")))
+            };
+
+            match &result {
+                Ok(_) => respond(&mut stream, "200 OK", &self.options.success_html).await,
+                Err(_) => respond(&mut stream, "400 Bad Request", &self.options.failure_html).await,
+            }
+            return result;
+        }
+    }
+}
+
+/// How long a connection to the redirect server may take to send its request head.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// The largest request head the redirect server reads.
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+/// Read a request's head, returning the stream and the request's target. The target is `None`
+/// when the request is malformed, too large or not sent within `REQUEST_TIMEOUT`.
+async fn read_request(mut stream: TcpStream) -> (TcpStream, Option<url::Url>) {
+    async fn target(stream: &mut TcpStream) -> Option<url::Url> {
+        let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await.ok()?;
+
+        let mut header = String::new();
+        while reader.read_line(&mut header).await.ok()? > 2 {
+            header.clear();
+        }
+        // A head cut off by the size limit ends without its blank line.
+        if header.is_empty() {
+            return None;
+        }
+
+        url::Url::parse(&("http://localhost".to_string() + request_line.split_whitespace().nth(1)?))
+            .ok()
+    }
+
+    let url = tokio::time::timeout(REQUEST_TIMEOUT, target(&mut stream))
+        .await
+        .ok()
+        .flatten();
+    (stream, url)
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, html: &str) {
+    let _ = stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {}\r\ncontent-type: text/html; charset=utf-8\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                html.len(),
+                html
+            )
+            .as_bytes(),
+        )
+        .await;
+}
+
+fn open_browser(url: &str) -> std::io::Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = std::process::Command::new("xdg-open");
+
+    command.arg(url).spawn().map(|_| ())
 }
 
 impl OToken {
+    /// A token without refresh token that expires `expires_in` from now.
+    pub(crate) fn expiring(access: String, expires_in: Option<Duration>) -> Self {
+        Self {
+            access,
+            expires_at: compute_expiration(expires_in),
+            ..Default::default()
+        }
+    }
+
+    /// How long until the access token expires, zero once it has. `None` when unknown. Like
+    /// `expires_at` this is a minute short, so it reaches zero just before Google stops
+    /// accepting the token.
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_at
+            .map(|at| at.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+
+    /// Whether `scope` is known to be granted.
+    pub fn has_scope(&self, scope: &CalendarScope) -> bool {
+        self.scopes.as_ref().is_some_and(|s| s.contains(scope))
+    }
+
+    /// Take the scopes from the redirect when the token response didn't list them.
+    fn or_scopes(mut self, scope: Option<&str>) -> Self {
+        if let Some(scope) = scope.filter(|s| self.scopes.is_none() && !s.is_empty()) {
+            self.scopes = Some(CalendarScopes::parse(scope));
+        }
+        self
+    }
+
     pub fn is_expired(&self) -> bool {
         if let Some(t) = self.expires_at.map(|e| e <= SystemTime::now()) {
             return t;
@@ -195,16 +768,34 @@ impl OToken {
     }
     pub fn take_over(&mut self, token: OToken) {
         self.access = token.access;
-        self.refresh = token.refresh;
         self.expires_at = token.expires_at;
+        // Refresh responses usually leave out the refresh token and may leave out the scopes,
+        // which are then unchanged.
+        if token.refresh.is_some() {
+            self.refresh = token.refresh;
+        }
+        if token.scopes.is_some() {
+            self.scopes = token.scopes;
+        }
+        if token.token_type.is_some() {
+            self.token_type = token.token_type;
+        }
+        if token.id_token.is_some() {
+            self.id_token = token.id_token;
+        }
     }
 }
 
-impl From<StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>> for OToken {
-    fn from(value: StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>) -> Self {
+impl From<GoogleTokenResponse> for OToken {
+    fn from(value: GoogleTokenResponse) -> Self {
         Self {
             access: value.access_token().secret().clone(),
             refresh: value.refresh_token().map(|r| r.secret().clone()),
+            scopes: value
+                .scopes()
+                .map(|s| s.iter().map(|s| CalendarScope::parse(s)).collect()),
+            token_type: Some(value.token_type().as_ref().to_string()),
+            id_token: value.extra_fields().id_token.clone(),
 
             expires_at: compute_expiration(value.expires_in()),
         }
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+api_enum! {
+    /// CalendarScope is an OAuth scope relevant to the Google Calendar API.
+    pub enum CalendarScope {
+        /// Full access to calendars, events and settings.
+        #[default]
+        Calendar => "https://www.googleapis.com/auth/calendar",
+        CalendarReadonly => "https://www.googleapis.com/auth/calendar.readonly",
+        Events => "https://www.googleapis.com/auth/calendar.events",
+        EventsReadonly => "https://www.googleapis.com/auth/calendar.events.readonly",
+        EventsOwned => "https://www.googleapis.com/auth/calendar.events.owned",
+        EventsOwnedReadonly => "https://www.googleapis.com/auth/calendar.events.owned.readonly",
+        Freebusy => "https://www.googleapis.com/auth/calendar.freebusy",
+        CalendarList => "https://www.googleapis.com/auth/calendar.calendarlist",
+        CalendarListReadonly => "https://www.googleapis.com/auth/calendar.calendarlist.readonly",
+        Calendars => "https://www.googleapis.com/auth/calendar.calendars",
+        CalendarsReadonly => "https://www.googleapis.com/auth/calendar.calendars.readonly",
+        SettingsReadonly => "https://www.googleapis.com/auth/calendar.settings.readonly",
+        UserInfoEmail => "https://www.googleapis.com/auth/userinfo.email",
+        UserInfoProfile => "https://www.googleapis.com/auth/userinfo.profile",
+        OpenId => "openid",
+    }
+}
+
+impl CalendarScope {
+    /// Google reports some scopes by their short alias in token responses.
+    pub(crate) fn parse(scope: &str) -> Self {
+        match scope {
+            "email" => Self::UserInfoEmail,
+            "profile" => Self::UserInfoProfile,
+            s => Self::from(s),
+        }
+    }
+}
+
+/// Any of these allows reading events.
+pub(crate) const EVENTS_READ: &[CalendarScope] = &[
+    CalendarScope::Calendar,
+    CalendarScope::CalendarReadonly,
+    CalendarScope::Events,
+    CalendarScope::EventsReadonly,
+    CalendarScope::EventsOwned,
+    CalendarScope::EventsOwnedReadonly,
+];
+/// Any of these allows changing events.
+pub(crate) const EVENTS_WRITE: &[CalendarScope] = &[
+    CalendarScope::Calendar,
+    CalendarScope::Events,
+    CalendarScope::EventsOwned,
+];
+/// Any of these allows reading the calendar list.
+pub(crate) const CALENDAR_LIST_READ: &[CalendarScope] = &[
+    CalendarScope::Calendar,
+    CalendarScope::CalendarReadonly,
+    CalendarScope::CalendarList,
+    CalendarScope::CalendarListReadonly,
+];
+/// Any of these allows changing the calendar list.
+pub(crate) const CALENDAR_LIST_WRITE: &[CalendarScope] =
+    &[CalendarScope::Calendar, CalendarScope::CalendarList];
+/// Any of these allows reading calendars.
+pub(crate) const CALENDARS_READ: &[CalendarScope] = &[
+    CalendarScope::Calendar,
+    CalendarScope::CalendarReadonly,
+    CalendarScope::Calendars,
+    CalendarScope::CalendarsReadonly,
+];
+/// Any of these allows changing calendars.
+pub(crate) const CALENDARS_WRITE: &[CalendarScope] =
+    &[CalendarScope::Calendar, CalendarScope::Calendars];
+/// Any of these allows reading the user's profile.
+pub(crate) const USER_INFO: &[CalendarScope] = &[
+    CalendarScope::UserInfoEmail,
+    CalendarScope::UserInfoProfile,
+    CalendarScope::OpenId,
+];
+
+/// CalendarScopes is a set of scopes, kept in the order they were added. It (de)serializes as the
+/// space separated list OAuth uses.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CalendarScopes(Vec<CalendarScope>);
+
+impl CalendarScopes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a scope, returns false when it was already in the set.
+    pub fn insert(&mut self, scope: CalendarScope) -> bool {
+        if self.contains(&scope) {
+            return false;
+        }
+        self.0.push(scope);
+        true
+    }
+
+    pub fn with(mut self, scope: CalendarScope) -> Self {
+        self.insert(scope);
+        self
+    }
+
+    pub fn contains(&self, scope: &CalendarScope) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// Whether at least one of `scopes` is in the set.
+    pub fn contains_any(&self, scopes: &[CalendarScope]) -> bool {
+        scopes.iter().any(|s| self.contains(s))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CalendarScope> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Parse a space separated list such as the `scope` of a token response.
+    pub fn parse(scopes: &str) -> Self {
+        scopes
+            .split_whitespace()
+            .map(CalendarScope::parse)
+            .collect()
+    }
+}
+
+impl FromIterator<CalendarScope> for CalendarScopes {
+    fn from_iter<I: IntoIterator<Item = CalendarScope>>(iter: I) -> Self {
+        let mut scopes = Self::new();
+        iter.into_iter().for_each(|s| {
+            scopes.insert(s);
+        });
+        scopes
+    }
+}
+
+impl From<&[CalendarScope]> for CalendarScopes {
+    fn from(scopes: &[CalendarScope]) -> Self {
+        scopes.iter().cloned().collect()
+    }
+}
+
+impl<const N: usize> From<[CalendarScope; N]> for CalendarScopes {
+    fn from(scopes: [CalendarScope; N]) -> Self {
+        scopes.into_iter().collect()
+    }
+}
+
+impl std::fmt::Display for CalendarScopes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scopes: Vec<&str> = self.iter().map(CalendarScope::to_str).collect();
+        write!(f, "{}", scopes.join(" "))
+    }
+}
+
+impl Serialize for CalendarScopes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CalendarScopes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::parse(&String::deserialize(deserializer)?))
+    }
+}
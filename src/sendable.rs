@@ -1,13 +1,47 @@
 use std::collections::BTreeMap;
 use url::Url;
 
-use super::ClientResult;
+use super::{CalendarScope, ClientResult};
 
-const BASE_URL: &str = "https://www.googleapis.com/calendar/v3";
+pub(crate) const BASE_URL: &str = "https://www.googleapis.com/calendar/v3";
 
-pub type QueryParams = BTreeMap<String, String>;
+/// QueryParams are ordered key/value pairs. Keys may repeat, e.g. for multiple extended
+/// property filters.
+pub type QueryParams = Vec<(String, String)>;
 pub type AdditionalProperties = BTreeMap<String, String>;
 
+/// Convert an options struct into query parameters. Unset options are skipped and sequences
+/// become one parameter per element.
+pub(crate) fn options_query(options: &impl serde::Serialize) -> ClientResult<QueryParams> {
+    fn value(v: serde_json::Value) -> Option<String> {
+        match v {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(s),
+            v => Some(v.to_string()),
+        }
+    }
+
+    let serde_json::Value::Object(map) = serde_json::to_value(options)? else {
+        return Ok(Vec::new());
+    };
+    let mut query = Vec::new();
+    for (key, v) in map {
+        match v {
+            serde_json::Value::Array(items) => {
+                query.extend(items.into_iter().filter_map(value).map(|v| (key.clone(), v)))
+            }
+            v => query.extend(value(v).map(|v| (key, v))),
+        }
+    }
+    Ok(query)
+}
+
+/// Set a query parameter, replacing any previous values of the key.
+pub(crate) fn set_query(query: &mut QueryParams, key: String, value: String) {
+    query.retain(|(k, _)| *k != key);
+    query.push((key, value));
+}
+
 /// Sendable is the trait you must implement to interact with the Client. This object is received
 /// by the client and is used to construct the request URL as well as manage the (de)serialization
 /// of the object.
@@ -17,7 +51,7 @@ where
 {
     fn path(&self, action: Option<String>) -> String;
 
-    fn query(&self) -> BTreeMap<String, String>;
+    fn query(&self) -> QueryParams;
 
     fn url(&self, action: Option<String>) -> ClientResult<Url> {
         Ok(Url::parse_with_params(
@@ -29,4 +63,11 @@ where
     fn body_bytes(&self) -> ClientResult<Vec<u8>> {
         Ok(serde_json::to_vec(self)?)
     }
+
+    /// The scopes of which at least one must be granted to read (GET) or `write` this. The client
+    /// returns `ClientError::InsufficientScope` without sending the request otherwise. Nothing is
+    /// checked when empty.
+    fn scopes(&self, _write: bool) -> &'static [CalendarScope] {
+        &[]
+    }
 }
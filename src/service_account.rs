@@ -0,0 +1,221 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+
+use super::{CalendarScope, CalendarScopes, ClientError, ClientResult, OToken};
+
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+/// Google accepts assertions valid for at most an hour.
+const ASSERTION_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// ServiceAccountKey is the JSON key file downloaded for a service account from the Google Cloud
+/// console.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct ServiceAccountKey {
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub private_key_id: Option<String>,
+    pub private_key: String,
+    pub client_email: String,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default = "default_token_uri")]
+    pub token_uri: String,
+}
+
+impl std::fmt::Debug for ServiceAccountKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceAccountKey")
+            .field("project_id", &self.project_id)
+            .field("private_key_id", &self.private_key_id)
+            .field("client_email", &self.client_email)
+            .field("token_uri", &self.token_uri)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServiceAccountKey {
+    pub fn from_json(json: &str) -> ClientResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> ClientResult<Self> {
+        let json = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            ClientError::AuthError(format!(
+                "cannot read service account key `{}`: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+}
+
+/// The claims of the JWT assertion exchanged for an access token.
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: String,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    scope: Option<String>,
+    token_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// ServiceAccount authenticates as a service account without a user in the loop, by signing an
+/// RS256 JWT assertion with the account's key (RFC 7523). With domain-wide delegation it can act
+/// as a Workspace user through `impersonate`.
+pub struct ServiceAccount {
+    key: ServiceAccountKey,
+    encoding_key: EncodingKey,
+    scopes: CalendarScopes,
+    subject: Option<String>,
+    token_url: String,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Debug for ServiceAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceAccount")
+            .field("key", &self.key)
+            .field("scopes", &self.scopes)
+            .field("subject", &self.subject)
+            .field("token_url", &self.token_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServiceAccount {
+    /// Fails when the private key is not an RSA key in PEM format.
+    pub fn new(key: ServiceAccountKey) -> ClientResult<Self> {
+        let encoding_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .map_err(|e| ClientError::AuthError(format!("invalid service account key: {}", e)))?;
+        Ok(Self {
+            token_url: key.token_uri.clone(),
+            key,
+            encoding_key,
+            scopes: CalendarScopes::from([CalendarScope::Calendar]),
+            subject: None,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> ClientResult<Self> {
+        Self::new(ServiceAccountKey::from_file(path)?)
+    }
+
+    /// Request these scopes instead of full calendar access.
+    pub fn with_scopes(mut self, scopes: impl Into<CalendarScopes>) -> Self {
+        self.scopes = scopes.into();
+        self
+    }
+
+    /// Act as this Workspace user. Requires domain-wide delegation for the requested scopes.
+    pub fn impersonate(mut self, subject: impl ToString) -> Self {
+        self.subject = Some(subject.to_string());
+        self
+    }
+
+    /// Exchange assertions at another token endpoint than the key file's, e.g. a local mock.
+    pub fn with_token_url(mut self, token_url: impl ToString) -> Self {
+        self.token_url = token_url.to_string();
+        self
+    }
+
+    pub fn client_email(&self) -> &str {
+        &self.key.client_email
+    }
+
+    /// A signed assertion for the configured scopes and subject, valid for an hour.
+    pub fn assertion(&self) -> ClientResult<String> {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let claims = Claims {
+            iss: &self.key.client_email,
+            scope: self.scopes.to_string(),
+            aud: &self.token_url,
+            iat,
+            exp: iat + ASSERTION_LIFETIME.as_secs(),
+            sub: self.subject.as_deref(),
+        };
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = self.key.private_key_id.clone();
+
+        jsonwebtoken::encode(&header, &claims, &self.encoding_key)
+            .map_err(|e| ClientError::AuthError(format!("cannot sign assertion: {}", e)))
+    }
+
+    /// Fetch a new access token. Service accounts get no refresh token, a new assertion is
+    /// exchanged every time.
+    pub async fn token(&self) -> ClientResult<OToken> {
+        let assertion = self.assertion()?;
+        let resp = self
+            .client
+            .post(&self.token_url)
+            .form(&[("grant_type", JWT_BEARER_GRANT), ("assertion", &assertion)])
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            return Err(match resp.json::<TokenError>().await {
+                Ok(e) => ClientError::AuthError(match e.error_description {
+                    Some(description) => format!("{}: {}", e.error, description),
+                    None => e.error,
+                }),
+                Err(_) => ClientError::AuthError(format!("token endpoint returned {}", status)),
+            });
+        }
+
+        let resp: TokenResponse = resp.json().await?;
+        let mut token =
+            OToken::expiring(resp.access_token, resp.expires_in.map(Duration::from_secs));
+        token.scopes = Some(match resp.scope {
+            Some(scope) => CalendarScopes::parse(&scope),
+            None => self.scopes.clone(),
+        });
+        token.token_type = resp.token_type;
+        Ok(token)
+    }
+
+    /// Replace `token` with a new one when it is missing or expired.
+    pub async fn refresh(&self, token: &mut OToken) -> ClientResult<()> {
+        if token.access.is_empty() || token.is_expired() {
+            self.renew(token).await?;
+        }
+        Ok(())
+    }
+
+    /// Replace `token` with a new one now.
+    pub async fn renew(&self, token: &mut OToken) -> ClientResult<()> {
+        token.take_over(self.token().await?);
+        Ok(())
+    }
+}
+
+fn default_token_uri() -> String {
+    GOOGLE_TOKEN_URL.to_string()
+}
@@ -0,0 +1,137 @@
+use std::{
+    fmt::Debug,
+    fs,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::{ClientError, ClientResult, OToken};
+
+/// TokenStore persists a token across restarts. Give one to `GCalClientBuilder::token_store` to
+/// have refreshed tokens saved automatically.
+pub trait TokenStore: Debug + Send + Sync {
+    /// The saved token, `None` when nothing was saved yet.
+    fn load(&self) -> ClientResult<Option<OToken>>;
+    fn save(&self, token: &OToken) -> ClientResult<()>;
+    /// Forget the saved token.
+    fn clear(&self) -> ClientResult<()>;
+}
+
+/// FileTokenStore keeps the token as JSON in a file only readable by the current user. The refresh
+/// token is readable in plaintext, see `EncryptedFileTokenStore` to seal it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn error(&self, action: &str, e: impl std::fmt::Display) -> ClientError {
+        store_error(action, &self.path, e)
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> ClientResult<Option<OToken>> {
+        match fs::read(&self.path) {
+            Ok(json) => Ok(Some(
+                serde_json::from_slice(&json).map_err(|e| self.error("parse", e))?,
+            )),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(self.error("read", e)),
+        }
+    }
+
+    fn save(&self, token: &OToken) -> ClientResult<()> {
+        let json = serde_json::to_vec_pretty(token)?;
+        replace_private(&self.path, &json).map_err(|e| self.error("write", e))
+    }
+
+    fn clear(&self) -> ClientResult<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(self.error("remove", e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn store_error(action: &str, path: &Path, e: impl std::fmt::Display) -> ClientError {
+    ClientError::TokenStoreError(format!("cannot {} `{}`: {}", action, path.display(), e))
+}
+
+/// Write to a temporary file next to `path` and rename it over, so a crash never leaves a half
+/// written token behind.
+pub(crate) fn replace_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    write_private(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+/// Create or truncate `path` with 0600 permissions on unix and write `contents`.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // The mode only applies to new files.
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        write_all(file, contents)
+    }
+    #[cfg(not(unix))]
+    write_all(options.open(path)?, contents)
+}
+
+fn write_all(mut file: fs::File, contents: &[u8]) -> std::io::Result<()> {
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// MemoryTokenStore keeps the token for the lifetime of the process, e.g. for tests.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<OToken>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(token: OToken) -> Self {
+        Self {
+            token: Mutex::new(Some(token)),
+        }
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> ClientResult<Option<OToken>> {
+        Ok(self.token.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn save(&self, token: &OToken) -> ClientResult<()> {
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = Some(token.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> ClientResult<()> {
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use super::{ClientResult, QueryParams, Sendable};
+use super::{scope, CalendarScope, ClientResult, GCalClient, QueryParams, Sendable};
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[serde(default)]
@@ -30,6 +30,10 @@ impl Sendable for UserInfo {
         self.query_string.clone()
     }
 
+    fn scopes(&self, _write: bool) -> &'static [CalendarScope] {
+        scope::USER_INFO
+    }
+
     fn url(&self, action: Option<String>) -> ClientResult<Url> {
         Ok(Url::parse_with_params(
             &format!(
@@ -41,3 +45,11 @@ impl Sendable for UserInfo {
         )?)
     }
 }
+
+impl GCalClient {
+    /// Fetch the profile of the account the token belongs to. Requires one of the email, profile
+    /// or openid scopes.
+    pub async fn user_info(&self) -> ClientResult<UserInfo> {
+        Ok(self.get(None, UserInfo::default()).await?.json().await?)
+    }
+}
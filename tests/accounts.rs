@@ -0,0 +1,174 @@
+//! Manages the clients of several accounts against a local server.
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use gcal_rs::*;
+
+mod common;
+
+const EVENTS: &str = r#"{"items": [{"id": "event"}]}"#;
+
+/// Answer user info by access token, refreshes on `/token`, and everything else with an event
+/// listing.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("POST /token") {
+        let access = if request.contains("refresh_token=refresh-b") {
+            "fresh-b"
+        } else {
+            "fresh-a"
+        };
+        return (
+            200,
+            format!(
+                r#"{{"access_token": "{}", "expires_in": 3599, "token_type": "Bearer"}}"#,
+                access
+            ),
+        );
+    }
+    if request.starts_with("GET /oauth2/v2/userinfo") {
+        return if request.contains("Bearer access-a") {
+            (200, r#"{"id": "1", "email": "a@example.com"}"#.to_string())
+        } else if request.contains("Bearer access-b") {
+            (200, r#"{"id": "2", "email": "b@example.com"}"#.to_string())
+        } else {
+            (401, "{}".to_string())
+        };
+    }
+    (200, EVENTS.to_string())
+}
+
+fn token(name: &str, expired: bool) -> OToken {
+    OToken {
+        access: format!("access-{}", name),
+        refresh: Some(format!("refresh-{}", name)),
+        expires_at: expired.then_some(SystemTime::UNIX_EPOCH),
+        ..Default::default()
+    }
+}
+
+fn manager(base: &str, idle_timeout: Duration) -> AccountManager {
+    AccountManager::builder(Some(Arc::new(common::oauth(base))))
+        .base_url(base)
+        .idle_timeout(idle_timeout)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn accounts_are_found_by_id_and_email() {
+    let (base, _) = common::serve(respond).await;
+    let manager = manager(&base, Duration::from_secs(60));
+
+    let a = manager.add(token("a", false)).await.unwrap();
+    let b = manager.add(token("b", false)).await.unwrap();
+    assert_eq!((a.id.as_str(), a.email.as_str()), ("1", "a@example.com"));
+    assert_eq!(b.id, "2");
+
+    let mut accounts = manager.accounts();
+    accounts.sort();
+    assert_eq!(accounts, ["1", "2"]);
+    assert!(Arc::ptr_eq(
+        &manager.client("1").unwrap(),
+        &manager.client("a@example.com").unwrap()
+    ));
+    assert!(matches!(
+        manager.client("c@example.com"),
+        Err(ClientError::UnknownAccount(_))
+    ));
+
+    assert_eq!(
+        manager.remove("b@example.com").await,
+        Some(token("b", false))
+    );
+    assert!(manager.client("2").is_err());
+}
+
+#[tokio::test]
+async fn emails_match_case_insensitively() {
+    let (base, _) = common::serve(respond).await;
+    let manager = manager(&base, Duration::from_secs(60));
+
+    manager.insert(
+        "1",
+        Some("Alice@Example.com".to_string()),
+        token("a", false),
+    );
+    assert!(Arc::ptr_eq(
+        &manager.client("alice@example.com").unwrap(),
+        &manager.client("ALICE@example.COM").unwrap()
+    ));
+    assert_eq!(
+        manager.token("alice@example.com").await.unwrap(),
+        token("a", false)
+    );
+    assert_eq!(
+        manager.remove("aLiCe@example.com").await,
+        Some(token("a", false))
+    );
+    assert!(manager.accounts().is_empty());
+    assert!(matches!(
+        manager.client("alice@example.com"),
+        Err(ClientError::UnknownAccount(_))
+    ));
+}
+
+#[tokio::test]
+async fn concurrent_requests_refresh_once() {
+    let (base, requests) = common::serve(respond).await;
+    let manager = Arc::new(manager(&base, Duration::from_secs(60)));
+    manager.insert("1", None, token("a", true));
+
+    let requests_sent: Vec<_> = (0..10)
+        .map(|_| {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                let events = manager.client("1").unwrap().event_client();
+                events.list("primary".into(), None).await.unwrap();
+            })
+        })
+        .collect();
+    for request in requests_sent {
+        request.await.unwrap();
+    }
+
+    assert_eq!(common::count(&requests, "POST /token"), 1);
+    assert_eq!(common::count(&requests, "GET /calendars"), 10);
+    assert_eq!(manager.token("1").await.unwrap().access, "fresh-a");
+}
+
+#[tokio::test]
+async fn idle_clients_are_evicted_and_rebuilt() {
+    let (base, requests) = common::serve(respond).await;
+    let manager = manager(&base, Duration::ZERO);
+    manager.insert("1", Some("a@example.com".into()), token("a", true));
+
+    let client = manager.client("a@example.com").unwrap();
+    client.refresh().await.unwrap();
+    assert_eq!(manager.evict_idle(), 1);
+    assert_eq!(manager.evict_idle(), 0);
+
+    // The rebuilt client continues with the refreshed token.
+    let rebuilt = manager.client("1").unwrap();
+    assert!(!Arc::ptr_eq(&client, &rebuilt));
+    assert_eq!(rebuilt.token().await.access, "fresh-a");
+    rebuilt.refresh().await.unwrap();
+    assert_eq!(common::count(&requests, "POST /token"), 1);
+}
+
+#[tokio::test]
+async fn refresh_all_refreshes_active_accounts() {
+    let (base, requests) = common::serve(respond).await;
+    let manager = manager(&base, Duration::from_secs(60));
+    manager.insert("1", None, token("a", true));
+    manager.insert("2", None, token("b", true));
+    manager.insert("3", None, token("c", true));
+    manager.client("1").unwrap();
+    manager.client("2").unwrap();
+
+    assert!(manager.refresh_all().await.is_empty());
+    assert_eq!(common::count(&requests, "POST /token"), 2);
+    assert_eq!(manager.token("2").await.unwrap().access, "fresh-b");
+    assert_eq!(manager.token("3").await.unwrap().access, "access-c");
+}
@@ -0,0 +1,116 @@
+//! String enums of the API, including values this crate does not know about yet.
+use serde_json::json;
+
+use gcal_rs::{types::*, *};
+
+mod common;
+
+#[test]
+fn birthday_events_round_trip() {
+    let properties = json!({
+        "contact": "people/c123",
+        "type": "custom",
+        "customTypeName": "Name day"
+    });
+    let event: Event = serde_json::from_value(json!({
+        "id": "birthday",
+        "eventType": "birthday",
+        "start": {"date": "2024-05-01"},
+        "end": {"date": "2024-05-02"},
+        "birthdayProperties": properties,
+    }))
+    .unwrap();
+
+    assert_eq!(event.event_type, EventType::Birthday);
+    let parsed = event.birthday_properties.as_ref().unwrap();
+    assert_eq!(parsed.typ, EventBirthdayType::Custom);
+    assert_eq!(parsed.custom_type_name.as_deref(), Some("Name day"));
+
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["eventType"], "birthday");
+    assert_eq!(json["birthdayProperties"], properties);
+}
+
+#[test]
+fn known_values_parse_into_their_variants() {
+    assert_eq!(EventType::from("fromGmail"), EventType::FromGmail);
+    assert_eq!(EventType::from("birthday"), EventType::Birthday);
+    assert_eq!(
+        EventBirthdayType::from("self"),
+        EventBirthdayType::SelfBirthday
+    );
+    assert_eq!(EventType::FromGmail.to_string(), "fromGmail");
+
+    let event: Event = serde_json::from_value(json!({"eventType": "fromGmail"})).unwrap();
+    assert_eq!(event.event_type, EventType::FromGmail);
+    assert_eq!(
+        serde_json::to_value(&event).unwrap()["eventType"],
+        "fromGmail"
+    );
+}
+
+#[test]
+fn unknown_values_survive_a_round_trip() {
+    let payload = json!({
+        "id": "event",
+        "eventType": "somethingNew",
+        "transparency": "translucent",
+        "visibility": "secret",
+        "birthdayProperties": {"type": "graduation"},
+    });
+    let event: Event = serde_json::from_value(payload).unwrap();
+
+    assert_eq!(
+        event.event_type,
+        EventType::Unknown("somethingNew".to_string())
+    );
+    assert_eq!(
+        event.transparency,
+        EventTransparency::Unknown("translucent".to_string())
+    );
+    assert_eq!(event.visibility.to_str(), "secret");
+
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["eventType"], "somethingNew");
+    assert_eq!(json["transparency"], "translucent");
+    assert_eq!(json["visibility"], "secret");
+    assert_eq!(json["birthdayProperties"]["type"], "graduation");
+}
+
+#[tokio::test]
+async fn request_enums_accept_unknown_values() {
+    let (base, requests) = common::serve(|_, _| (200, r#"{"items": []}"#.to_string())).await;
+    let events = common::client(&base).event_client();
+
+    assert_eq!(SendUpdates::from("externalOnly"), SendUpdates::ExternalOnly);
+    assert_eq!(SendUpdates::default().to_string(), "all");
+    assert_eq!(EventTypes::from("focusTime"), EventTypes::FocusTime);
+    assert_eq!(EventTypes::default(), EventTypes::Default);
+
+    let options = EventListOptions {
+        event_types: Some(vec![
+            EventTypes::Birthday,
+            EventTypes::Unknown("somethingNew".to_string()),
+        ]),
+        ..Default::default()
+    };
+    events.list("primary".into(), Some(options)).await.unwrap();
+    let mut event = Event::default();
+    event.id = "event".to_string();
+    event.calendar_id = "primary".to_string();
+    let options = EventRequestOptions::send_updates(SendUpdates::Unknown("later".to_string()));
+    events.delete(event, Some(options)).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let query = common::query(&requests[0]);
+    let types: Vec<_> = query
+        .iter()
+        .filter(|(k, _)| k == "eventTypes")
+        .map(|(_, v)| v.as_str())
+        .collect();
+    assert_eq!(types, ["birthday", "somethingNew"]);
+    assert_eq!(
+        common::param(&common::query(&requests[1]), "sendUpdates").as_deref(),
+        Some("later")
+    );
+}
@@ -0,0 +1,131 @@
+//! Attaches Google Drive files to events.
+use gcal_rs::{
+    types::{EventAttachment, SendUpdates},
+    *,
+};
+
+mod common;
+
+const FILE: &str = "https://drive.google.com/file/d/abc123/view?usp=sharing";
+
+fn event() -> Event {
+    let mut event = Event::default();
+    event.id = "event".to_string();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+fn respond(_: &str, _: usize) -> (u16, String) {
+    (200, r#"{"id": "event"}"#.to_string())
+}
+
+#[test]
+fn drive_file_ids_are_taken_from_the_url() {
+    let cases = [
+        (FILE, Some("abc123")),
+        ("https://drive.google.com/open?id=abc123", Some("abc123")),
+        (
+            "https://drive.google.com/uc?export=download&id=abc123#top",
+            Some("abc123"),
+        ),
+        (
+            "https://docs.google.com/document/d/abc123/edit",
+            Some("abc123"),
+        ),
+        ("https://drive.google.com/file/d/", None),
+        ("https://example.com/report.pdf", None),
+    ];
+    for (url, id) in cases {
+        assert_eq!(
+            EventAttachment::from_drive_url(url).drive_file_id(),
+            id,
+            "{}",
+            url
+        );
+    }
+
+    let mut attachment = EventAttachment::from_drive_url("https://example.com/report.pdf");
+    attachment.file_id = Some("xyz".to_string());
+    assert_eq!(attachment.drive_file_id(), Some("xyz"));
+}
+
+#[test]
+fn attachments_are_matched_by_drive_file_id() {
+    let mut event = event();
+    assert!(event.add_attachment(EventAttachment::from_drive_url(FILE).with_title("Notes")));
+    assert!(!event.add_attachment(EventAttachment::from_drive_url(
+        "https://drive.google.com/open?id=abc123"
+    )));
+    assert_eq!(event.attachments.len(), 1);
+
+    let removed = event.remove_attachment("abc123").unwrap();
+    assert_eq!(removed.title.as_deref(), Some("Notes"));
+    assert!(event.attachments.is_empty());
+    assert!(event.remove_attachment(FILE).is_none());
+}
+
+#[tokio::test]
+async fn update_attachments_only_sends_the_attachments() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let mut event = event();
+    event.summary = "Planning".to_string();
+    event.add_attachment(EventAttachment::from_drive_url(FILE).with_mime_type("application/pdf"));
+    events
+        .update_attachments(
+            &event,
+            Some(EventRequestOptions::send_updates(SendUpdates::All)),
+        )
+        .await
+        .unwrap();
+
+    // Removing the last attachment still sends the empty list.
+    event.remove_attachment(FILE);
+    events.update_attachments(&event, None).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("PATCH /calendars/primary/events/event"));
+    let query = common::query(&requests[0]);
+    assert_eq!(
+        common::param(&query, "supportsAttachments").as_deref(),
+        Some("true")
+    );
+    assert_eq!(common::param(&query, "sendUpdates").as_deref(), Some("all"));
+    assert_eq!(
+        common::body(&requests[0]),
+        serde_json::json!({"attachments": [{"fileUrl": FILE, "mimeType": "application/pdf"}]})
+    );
+
+    let query = common::query(&requests[1]);
+    assert_eq!(
+        common::param(&query, "supportsAttachments").as_deref(),
+        Some("true")
+    );
+    assert_eq!(
+        common::body(&requests[1]),
+        serde_json::json!({"attachments": []})
+    );
+}
+
+#[tokio::test]
+async fn update_only_supports_attachments_when_there_are_some() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    events.update(event(), None).await.unwrap();
+    let mut attached = event();
+    attached.add_attachment(EventAttachment::from_drive_url(FILE));
+    events.update(attached, None).await.unwrap();
+    let options = EventRequestOptions {
+        supports_attachments: Some(true),
+        ..Default::default()
+    };
+    events.update(event(), Some(options)).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let supports = |i: usize| common::param(&common::query(&requests[i]), "supportsAttachments");
+    assert_eq!(supports(0), None);
+    assert_eq!(supports(1).as_deref(), Some("true"));
+    assert_eq!(supports(2).as_deref(), Some("true"));
+}
@@ -0,0 +1,143 @@
+//! Counts attendee responses and responds to invitations.
+use serde_json::json;
+
+use gcal_rs::{types::EventResponseStatus, *};
+
+mod common;
+
+fn event(attendees: serde_json::Value) -> Event {
+    let mut event: Event = serde_json::from_value(json!({
+        "id": "event",
+        "organizer": {"email": "boss@example.com"},
+        "attendees": attendees,
+    }))
+    .unwrap();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+fn invitation() -> Event {
+    event(json!([
+        {"email": "boss@example.com", "organizer": true, "responseStatus": "accepted"},
+        {"email": "me@example.com", "self": true, "responseStatus": "needsAction"},
+        {"email": "room@resource.calendar.google.com", "resource": true, "responseStatus": "accepted"},
+    ]))
+}
+
+fn respond(_: &str, _: usize) -> (u16, String) {
+    (200, r#"{"id": "event"}"#.to_string())
+}
+
+#[test]
+fn summary_counts_every_response() {
+    let event = event(json!([
+        {"email": "a@example.com", "responseStatus": "accepted"},
+        {"email": "b@example.com", "responseStatus": "accepted"},
+        {"email": "c@example.com", "responseStatus": "declined"},
+        {"email": "d@example.com", "responseStatus": "tentative"},
+        {"email": "e@example.com", "responseStatus": "needsAction"},
+        {"email": "f@example.com", "responseStatus": "somethingNew"},
+    ]));
+    assert_eq!(
+        event.attendee_summary(),
+        AttendeeSummary {
+            accepted: 2,
+            declined: 1,
+            tentative: 1,
+            pending: 2,
+        }
+    );
+}
+
+#[test]
+fn summary_skips_resources_and_counts_the_organizer() {
+    assert_eq!(
+        invitation().attendee_summary(),
+        AttendeeSummary {
+            accepted: 1,
+            pending: 1,
+            ..Default::default()
+        }
+    );
+
+    // An event without guests has no attendee list, not even the organizer.
+    let organizer_only = event(json!([]));
+    assert_eq!(
+        organizer_only.attendee_summary(),
+        AttendeeSummary::default()
+    );
+    let mut rooms_only = organizer_only.clone();
+    rooms_only.add_resource("room@resource.calendar.google.com");
+    assert_eq!(rooms_only.attendee_summary(), AttendeeSummary::default());
+}
+
+#[tokio::test]
+async fn respond_only_changes_the_own_entry() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let event = invitation();
+    assert_eq!(event.self_attendee().unwrap().email, "me@example.com");
+    events
+        .respond(
+            &event,
+            EventResponseStatus::Tentative,
+            Some("Might be late".to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("PATCH /calendars/primary/events/event"));
+    // Only the own entry is sent, so a stale copy can't revert the other guests.
+    assert_eq!(
+        common::body(&requests[0]),
+        json!({
+            "attendees": [{
+                "email": "me@example.com",
+                "self": true,
+                "responseStatus": "tentative",
+                "comment": "Might be late",
+            }],
+            "attendeesOmitted": true,
+        })
+    );
+}
+
+#[tokio::test]
+async fn respond_keeps_omitted_attendees() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    // Fetched with maxAttendees, the list only holds the user.
+    let mut event = event(json!([
+        {"email": "me@example.com", "self": true, "responseStatus": "needsAction"},
+    ]));
+    event.attendees_omitted = true;
+    events
+        .respond(&event, EventResponseStatus::Accepted, None, None)
+        .await
+        .unwrap();
+
+    let body = common::body(&requests.lock().unwrap()[0]);
+    assert_eq!(body["attendeesOmitted"], true);
+    assert_eq!(
+        body["attendees"],
+        json!([{"email": "me@example.com", "self": true, "responseStatus": "accepted"}])
+    );
+}
+
+#[tokio::test]
+async fn respond_requires_an_invitation() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let organizer_only = event(json!([]));
+    let err = events
+        .respond(&organizer_only, EventResponseStatus::Accepted, None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClientError::NotAnAttendee(id) if id == "event"));
+    assert!(requests.lock().unwrap().is_empty());
+}
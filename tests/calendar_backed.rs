@@ -0,0 +1,153 @@
+//! Stores your own types as events, found again by their external ID.
+use gcal_rs::*;
+
+mod common;
+
+#[derive(Debug, PartialEq)]
+struct Ticket {
+    id: String,
+    title: String,
+}
+
+impl CalendarBacked for Ticket {
+    fn external_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn to_event(&self, event: &mut Event) {
+        event.summary = self.title.clone();
+    }
+
+    fn from_event(event: &Event) -> ClientResult<Self> {
+        Ok(Self {
+            id: event.external_id().unwrap_or_default().to_string(),
+            title: event.summary.clone(),
+        })
+    }
+
+    fn metadata(&self) -> AdditionalProperties {
+        AdditionalProperties::from([("source".to_string(), "tracker".to_string())])
+    }
+}
+
+fn ticket(id: &str) -> Ticket {
+    Ticket {
+        id: id.to_string(),
+        title: "Fix the build".to_string(),
+    }
+}
+
+/// Only ticket `T-1` has an event, `existing`.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    let filter = common::param(&common::query(request), "privateExtendedProperty");
+    if request.starts_with("GET ") {
+        return match filter.as_deref() {
+            Some("externalId=T-1") => (
+                200,
+                r#"{"items": [{
+                    "id": "existing",
+                    "summary": "Old title",
+                    "location": "Room 1",
+                    "extendedProperties": {"private": {"externalId": "T-1"}}
+                }]}"#
+                    .to_string(),
+            ),
+            _ => (200, r#"{"items": []}"#.to_string()),
+        };
+    }
+    if request.starts_with("DELETE ") {
+        return (204, String::new());
+    }
+    let id = if request.starts_with("POST ") {
+        "created"
+    } else {
+        "existing"
+    };
+    (200, format!(r#"{{"id": "{}"}}"#, id))
+}
+
+#[tokio::test]
+async fn upsert_updates_the_event_found_by_external_id() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let event = events
+        .upsert("primary".into(), &ticket("T-1"), None)
+        .await
+        .unwrap();
+    assert_eq!(event.id, "existing");
+    assert_eq!(event.calendar_id, "primary");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].starts_with("PUT /calendars/primary/events/existing"));
+    let body = common::body(&requests[1]);
+    assert_eq!(body["summary"], "Fix the build");
+    // Fields the object does not write are kept.
+    assert_eq!(body["location"], "Room 1");
+    assert_eq!(
+        body["extendedProperties"]["private"],
+        serde_json::json!({"externalId": "T-1", "source": "tracker"})
+    );
+}
+
+#[tokio::test]
+async fn upsert_inserts_when_no_event_exists() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let event = events
+        .upsert("primary".into(), &ticket("T-2"), None)
+        .await
+        .unwrap();
+    assert_eq!(event.id, "created");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].starts_with("POST /calendars/primary/events"));
+    // Only what the object wrote is sent, not the defaults of an empty event.
+    assert_eq!(
+        common::body(&requests[1]),
+        serde_json::json!({
+            "summary": "Fix the build",
+            "extendedProperties": {"private": {"externalId": "T-2", "source": "tracker"}},
+        })
+    );
+}
+
+#[tokio::test]
+async fn load_rebuilds_the_object() {
+    let (base, _) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let loaded: Option<Ticket> = events.load("primary".into(), "T-1").await.unwrap();
+    assert_eq!(
+        loaded,
+        Some(Ticket {
+            id: "T-1".to_string(),
+            title: "Old title".to_string(),
+        })
+    );
+    let missing: Option<Ticket> = events.load("primary".into(), "T-2").await.unwrap();
+    assert_eq!(missing, None);
+}
+
+#[tokio::test]
+async fn delete_by_external_id_reports_missing_events() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    assert!(!events
+        .delete_by_external_id("primary".into(), "T-2", None)
+        .await
+        .unwrap());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    assert!(events
+        .delete_by_external_id("primary".into(), "T-1", None)
+        .await
+        .unwrap());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[2].starts_with("DELETE /calendars/primary/events/existing"));
+}
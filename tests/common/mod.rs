@@ -0,0 +1,137 @@
+//! A local HTTP server standing in for Google's endpoints, and clients pointed at it.
+#![allow(dead_code)]
+use std::sync::{Arc, Mutex};
+
+use gcal_rs::*;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Every request received, in order, as raw HTTP.
+pub type Requests = Arc<Mutex<Vec<String>>>;
+
+/// Answer each request with the status and JSON body `respond` returns for it. `respond` gets the
+/// raw request and how many requests came before it. Returns the base URL and the requests.
+///
+/// 401 responses carry the `invalid_token` challenge Google sends for rejected access tokens.
+pub async fn serve(
+    respond: impl Fn(&str, usize) -> (u16, String) + Send + Sync + 'static,
+) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Requests::default();
+    let seen = requests.clone();
+    let respond = Arc::new(respond);
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let seen = seen.clone();
+            let respond = respond.clone();
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                // Read the headers and as much of the body as announced.
+                loop {
+                    let n = stream.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                let (k, v) = l.split_once(':')?;
+                                k.eq_ignore_ascii_case("content-length")
+                                    .then(|| v.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if buf.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                let request = String::from_utf8_lossy(&buf).to_string();
+                let (status, body) = {
+                    let mut seen = seen.lock().unwrap();
+                    let response = respond(&request, seen.len());
+                    seen.push(request);
+                    response
+                };
+                let challenge = if status == 401 {
+                    "www-authenticate: Bearer realm=\"https://accounts.google.com/\", error=\"invalid_token\"\r\n"
+                } else {
+                    ""
+                };
+                let response = format!(
+                    "HTTP/1.1 {} X\r\ncontent-type: application/json\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    challenge,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    (format!("http://{}", addr), requests)
+}
+
+/// How many of the requests start with `prefix`, e.g. `POST /token`.
+pub fn count(requests: &Requests, prefix: &str) -> usize {
+    requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.starts_with(prefix))
+        .count()
+}
+
+/// The decoded query parameters of a raw HTTP request, in order.
+pub fn query(request: &str) -> Vec<(String, String)> {
+    let target = request.split(' ').nth(1).unwrap_or_default();
+    url::Url::parse(&format!("http://localhost{}", target))
+        .unwrap()
+        .query_pairs()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect()
+}
+
+/// The first value of `key` in a decoded query.
+pub fn param(query: &[(String, String)], key: &str) -> Option<String> {
+    query.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+}
+
+/// The JSON body of a raw HTTP request.
+pub fn body(request: &str) -> serde_json::Value {
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    serde_json::from_str(body).unwrap()
+}
+
+/// OAuth configuration whose endpoints are all served at `base`: `/token`, `/device/code`,
+/// `/revoke` and `/tokeninfo`.
+pub fn oauth(base: &str) -> OAuth {
+    OAuth::with_endpoints(
+        "client",
+        "secret",
+        "http://localhost:5000/auth",
+        OAuthEndpoints {
+            token_url: format!("{}/token", base),
+            device_authorization_url: format!("{}/device/code", base),
+            revocation_url: format!("{}/revoke", base),
+            tokeninfo_url: format!("{}/tokeninfo", base),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+/// A client sending every request to `base` with a token that never expires.
+pub fn client(base: &str) -> Arc<GCalClient> {
+    let token = OToken {
+        access: "test".to_string(),
+        ..Default::default()
+    };
+    GCalClient::builder(token).base_url(base).build().unwrap()
+}
@@ -0,0 +1,139 @@
+//! Requests Google Meet conferences and waits for them to be created.
+use std::time::Duration;
+
+use gcal_rs::{types::EventConferenceSolutionKeyType, *};
+
+mod common;
+
+fn conference(status: &str, entry_points: bool) -> String {
+    let entry_points = if entry_points {
+        r#", "entryPoints": [{"entryPointType": "video", "uri": "https://meet.google.com/abc"}]"#
+    } else {
+        ""
+    };
+    format!(
+        r#"{{"id": "event", "conferenceData": {{"createRequest": {{
+            "requestId": "req",
+            "conferenceSolutionKey": {{"type": "hangoutsMeet"}},
+            "status": {{"statusCode": "{}"}}
+        }}{}}}}}"#,
+        status, entry_points
+    )
+}
+
+/// Pending on the first poll, created on the second.
+fn created(_: &str, seen: usize) -> (u16, String) {
+    match seen {
+        0 => (200, conference("pending", false)),
+        _ => (200, conference("success", true)),
+    }
+}
+
+fn failed(_: &str, _: usize) -> (u16, String) {
+    (200, conference("failure", false))
+}
+
+fn pending(_: &str, _: usize) -> (u16, String) {
+    (200, conference("pending", false))
+}
+
+/// The event as returned by the insert, with its conference still pending.
+fn event() -> Event {
+    let mut event: Event = serde_json::from_str(&conference("pending", false)).unwrap();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+#[test]
+fn request_conference_asks_for_a_new_conference() {
+    let mut first = Event::default();
+    first.request_conference(EventConferenceSolutionKeyType::HangoutsMeet);
+    let mut second = Event::default();
+    second.request_conference(EventConferenceSolutionKeyType::HangoutsMeet);
+
+    let json = serde_json::to_value(&first).unwrap();
+    let request = &json["conferenceData"]["createRequest"];
+    assert_eq!(request["conferenceSolutionKey"]["type"], "hangoutsMeet");
+    let id = |e: &Event| {
+        e.conference_data
+            .as_ref()
+            .and_then(|c| c.create_request.as_ref())
+            .map(|r| r.request_id.clone())
+            .unwrap()
+    };
+    assert!(!id(&first).is_empty());
+    assert_ne!(id(&first), id(&second));
+}
+
+#[tokio::test]
+async fn request_conference_sends_conference_data_version() {
+    let (base, requests) = common::serve(pending).await;
+    let events = common::client(&base).event_client();
+
+    let mut event = Event::default();
+    event.calendar_id = "primary".to_string();
+    event.request_conference(EventConferenceSolutionKeyType::HangoutsMeet);
+    events.insert(event, None).await.unwrap();
+
+    let query = common::query(&requests.lock().unwrap()[0]);
+    assert_eq!(
+        common::param(&query, "conferenceDataVersion").as_deref(),
+        Some("1")
+    );
+}
+
+#[tokio::test]
+async fn await_conference_polls_until_created() {
+    let (base, requests) = common::serve(created).await;
+    let events = common::client(&base).event_client();
+
+    let entry_points = events
+        .await_conference(&event(), Duration::from_millis(1), 5)
+        .await
+        .unwrap();
+    assert_eq!(
+        entry_points[0].uri.as_deref(),
+        Some("https://meet.google.com/abc")
+    );
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|r| r.starts_with("GET /calendars/primary/events/event")));
+}
+
+#[tokio::test]
+async fn await_conference_reports_failures() {
+    let (base, requests) = common::serve(failed).await;
+    let events = common::client(&base).event_client();
+
+    let err = events
+        .await_conference(&event(), Duration::from_millis(1), 5)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, ClientError::ConferenceFailed(m) if m.contains("creation failed")),
+        "{}",
+        err
+    );
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn await_conference_gives_up_after_its_attempts() {
+    let (base, requests) = common::serve(pending).await;
+    let events = common::client(&base).event_client();
+
+    let err = events
+        .await_conference(&event(), Duration::from_millis(1), 3)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, ClientError::ConferenceFailed(m) if m.contains("still pending")),
+        "{}",
+        err
+    );
+    // The first attempt looks at the event as given.
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
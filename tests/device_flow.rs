@@ -0,0 +1,77 @@
+//! Runs the OAuth device flow against a local authorization server.
+use gcal_rs::*;
+
+mod common;
+
+const DEVICE_CODE: &str = r#"{
+    "device_code": "device-1",
+    "user_code": "ABCD-EFGH",
+    "verification_url": "https://www.google.com/device",
+    "expires_in": 60,
+    "interval": 1
+}"#;
+
+/// The user approves on the second poll.
+fn approve(request: &str, seen: usize) -> (u16, String) {
+    match (request.starts_with("POST /device/code"), seen) {
+        (true, _) => (200, DEVICE_CODE.to_string()),
+        (false, 1) => (428, r#"{"error": "authorization_pending"}"#.to_string()),
+        (false, _) => (
+            200,
+            r#"{
+                "access_token": "access",
+                "refresh_token": "refresh",
+                "expires_in": 3599,
+                "token_type": "Bearer",
+                "scope": "https://www.googleapis.com/auth/calendar.readonly"
+            }"#
+            .to_string(),
+        ),
+    }
+}
+
+fn deny(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("POST /device/code") {
+        (200, DEVICE_CODE.to_string())
+    } else {
+        (400, r#"{"error": "access_denied"}"#.to_string())
+    }
+}
+
+#[tokio::test]
+async fn device_flow_polls_until_approved() {
+    let (base, requests) = common::serve(approve).await;
+    let oauth = common::oauth(&base).with_scopes([CalendarScope::CalendarReadonly]);
+
+    let authorization = oauth.device_code().await.unwrap();
+    assert_eq!(authorization.user_code(), "ABCD-EFGH");
+    assert_eq!(
+        authorization.verification_url(),
+        "https://www.google.com/device"
+    );
+    assert_eq!(authorization.interval(), std::time::Duration::from_secs(1));
+
+    let token = oauth.device_token(&authorization).await.unwrap();
+    assert_eq!(token.access, "access");
+    assert_eq!(token.refresh.as_deref(), Some("refresh"));
+    assert_eq!(
+        token.scopes,
+        Some(CalendarScopes::from([CalendarScope::CalendarReadonly]))
+    );
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(
+        requests[0].contains("scope=https%3A%2F%2Fwww.googleapis.com%2Fauth%2Fcalendar.readonly")
+    );
+    assert!(requests[1].contains("device_code=device-1"));
+}
+
+#[tokio::test]
+async fn device_flow_reports_denial() {
+    let (base, _) = common::serve(deny).await;
+    let oauth = common::oauth(&base).with_scopes([CalendarScope::CalendarReadonly]);
+
+    let authorization = oauth.device_code().await.unwrap();
+    assert!(oauth.device_token(&authorization).await.is_err());
+}
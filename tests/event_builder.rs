@@ -0,0 +1,169 @@
+//! Builds new events, validating them locally and sending only what was set.
+use chrono::{NaiveDate, TimeZone, Utc};
+use serde_json::json;
+
+use gcal_rs::{
+    types::{EventCalendarDate, EventStatus},
+    *,
+};
+
+mod common;
+
+fn respond(_: &str, _: usize) -> (u16, String) {
+    (200, r#"{"id": "event"}"#.to_string())
+}
+
+fn planning() -> EventBuilder {
+    let start = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+    Event::builder("Planning")
+        .calendar("primary")
+        .time(&start, &(start + chrono::Duration::hours(1)))
+}
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+}
+
+fn invalid(builder: EventBuilder) -> EventValidationError {
+    match builder.build() {
+        Err(ClientError::InvalidEvent(err)) => err,
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn built_events_only_send_what_was_set() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let event = planning()
+        .attendee("sam@example.com")
+        .reminder(ReminderMethod::PopUp, 10)
+        .build()
+        .unwrap();
+    events.insert(event, None).await.unwrap();
+    let event = planning()
+        .status(EventStatus::Tentative)
+        .guests_can_see_other_guests(false)
+        .build()
+        .unwrap();
+    events.insert(event, None).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let body = common::body(&requests[0]);
+    let mut keys: Vec<_> = body.as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, ["attendees", "end", "reminders", "start", "summary"]);
+    assert_eq!(
+        body["reminders"],
+        json!({"overrides": [{"method": "popup", "minutes": 10}], "useDefault": false})
+    );
+
+    let body = common::body(&requests[1]);
+    assert_eq!(body["status"], "tentative");
+    assert_eq!(body["guestsCanSeeOtherGuests"], false);
+    assert!(body.get("guestsCanInviteOthers").is_none());
+}
+
+#[tokio::test]
+async fn updates_send_fields_set_back_to_their_default() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let mut event: Event = serde_json::from_value(json!({
+        "id": "event",
+        "locked": true,
+        "guestsCanSeeOtherGuests": false,
+        "transparency": "transparent",
+    }))
+    .unwrap();
+    event.calendar_id = "primary".to_string();
+    event.locked = false;
+    event.guests_can_see_other_guests = true;
+    event.transparency = types::EventTransparency::Opaque;
+    events.update(event, None).await.unwrap();
+
+    let body = common::body(&requests.lock().unwrap()[0]);
+    assert_eq!(body["locked"], false);
+    assert_eq!(body["guestsCanSeeOtherGuests"], true);
+    assert_eq!(body["transparency"], "opaque");
+}
+
+#[test]
+fn valid_events_build() {
+    planning().build().unwrap();
+    Event::builder("Offsite")
+        .all_day(day(4), day(5))
+        .recurrence("RRULE:FREQ=YEARLY")
+        .build()
+        .unwrap();
+    planning()
+        .time_zone("Europe/Zurich")
+        .recurrence("RRULE:FREQ=WEEKLY")
+        .build()
+        .unwrap();
+}
+
+#[test]
+fn each_validation_error_is_reported() {
+    assert_eq!(
+        invalid(Event::builder("No start")),
+        EventValidationError::MissingStart
+    );
+    assert_eq!(
+        invalid(Event::builder("No end").start(EventCalendarDate::from_date(day(4)))),
+        EventValidationError::MissingEnd
+    );
+    assert_eq!(
+        invalid(planning().end(EventCalendarDate::from_date(day(5)))),
+        EventValidationError::MixedAllDay
+    );
+    assert_eq!(
+        invalid(Event::builder("Backwards").all_day(day(5), day(3))),
+        EventValidationError::EndNotAfterStart {
+            start: "2024-03-05".to_string(),
+            end: "2024-03-04".to_string(),
+        }
+    );
+    let start = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+    assert!(matches!(
+        invalid(Event::builder("Instant").time(&start, &start)),
+        EventValidationError::EndNotAfterStart { .. }
+    ));
+    assert_eq!(
+        invalid(planning().end(EventCalendarDate {
+            date_time: Some("soon".to_string()),
+            ..Default::default()
+        })),
+        EventValidationError::InvalidDate("soon".to_string())
+    );
+    assert_eq!(
+        invalid(planning().recurrence("RRULE:FREQ=WEEKLY")),
+        EventValidationError::MissingTimeZone
+    );
+
+    let mut reminders = planning();
+    for minutes in 0..6 {
+        reminders = reminders.reminder(ReminderMethod::EMail, minutes);
+    }
+    assert_eq!(
+        invalid(reminders),
+        EventValidationError::TooManyReminders(6)
+    );
+    assert_eq!(
+        invalid(planning().reminder(ReminderMethod::PopUp, 40321)),
+        EventValidationError::ReminderOutOfRange(40321)
+    );
+    for email in [
+        "sam",
+        "sam@example",
+        "@example.com",
+        "sam@@example.com",
+        "s am@example.com",
+    ] {
+        assert_eq!(
+            invalid(planning().attendee(email)),
+            EventValidationError::InvalidEmail(email.to_string())
+        );
+    }
+}
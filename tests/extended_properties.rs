@@ -0,0 +1,60 @@
+//! Stores and filters by private and shared extended properties.
+use gcal_rs::*;
+
+mod common;
+
+fn respond(_: &str, _: usize) -> (u16, String) {
+    (200, r#"{"items": []}"#.to_string())
+}
+
+#[tokio::test]
+async fn list_sends_every_property_filter() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let options = EventListOptions::default()
+        .with_private_property("project", "apollo")
+        .with_private_property("owner", "a=b")
+        .with_shared_property("room", "42");
+    events.list("primary".into(), Some(options)).await.unwrap();
+
+    let query = common::query(&requests.lock().unwrap()[0]);
+    let values = |key: &str| -> Vec<&str> {
+        query
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    };
+    assert_eq!(
+        values("privateExtendedProperty"),
+        ["project=apollo", "owner=a=b"]
+    );
+    assert_eq!(values("sharedExtendedProperty"), ["room=42"]);
+}
+
+#[test]
+fn properties_round_trip_through_serde() {
+    let mut event = Event::default();
+    event.set_private_property("project", "apollo");
+    event.set_shared_property("room", "42");
+
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(
+        json["extendedProperties"],
+        serde_json::json!({"private": {"project": "apollo"}, "shared": {"room": "42"}})
+    );
+
+    let mut parsed: Event = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.private_property("project"), Some("apollo"));
+    assert_eq!(parsed.shared_property("room"), Some("42"));
+    assert_eq!(parsed.private_property("room"), None);
+    assert_eq!(parsed.extended_properties, event.extended_properties);
+
+    assert_eq!(
+        parsed.remove_private_property("project").as_deref(),
+        Some("apollo")
+    );
+    assert_eq!(parsed.private_property("project"), None);
+    assert_eq!(parsed.remove_shared_property("missing"), None);
+}
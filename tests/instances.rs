@@ -0,0 +1,73 @@
+//! Pages through the instances of a recurring event.
+use chrono::{DateTime, Local, TimeZone, Utc};
+
+use gcal_rs::*;
+
+mod common;
+
+/// Two pages of instances, linked by `nextPageToken`.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.contains("pageToken=second") {
+        (200, r#"{"items": [{"id": "series_3"}]}"#.to_string())
+    } else {
+        (
+            200,
+            r#"{"items": [{"id": "series_1"}, {"id": "series_2"}], "nextPageToken": "second"}"#
+                .to_string(),
+        )
+    }
+}
+
+fn series() -> Event {
+    let mut event = Event::default();
+    event.id = "series".to_string();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+fn local(y: i32, m: u32, d: u32) -> DateTime<Local> {
+    Utc.with_ymd_and_hms(y, m, d, 0, 0, 0)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+#[tokio::test]
+async fn instances_all_follows_every_page() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let options = EventInstancesOptions {
+        time_min: Some(local(2024, 1, 1)),
+        time_max: Some(local(2024, 2, 1)),
+        original_start: Some("2024-01-08T09:00:00Z".to_string()),
+        ..Default::default()
+    };
+    let instances = events
+        .instances_all(&series(), Some(options))
+        .await
+        .unwrap();
+
+    let ids: Vec<&str> = instances.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, ["series_1", "series_2", "series_3"]);
+    assert!(instances.iter().all(|e| e.calendar_id == "primary"));
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for (i, request) in requests.iter().enumerate() {
+        assert!(request.starts_with("GET /calendars/primary/events/series/instances?"));
+        let query = common::query(request);
+        let instant = |key| {
+            DateTime::parse_from_rfc3339(&common::param(&query, key).unwrap())
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        assert_eq!(instant("timeMin"), local(2024, 1, 1));
+        assert_eq!(instant("timeMax"), local(2024, 2, 1));
+        assert_eq!(
+            common::param(&query, "originalStart").as_deref(),
+            Some("2024-01-08T09:00:00Z")
+        );
+        let page_token = common::param(&query, "pageToken");
+        assert_eq!(page_token.as_deref(), (i == 1).then_some("second"));
+    }
+}
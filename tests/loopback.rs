@@ -0,0 +1,201 @@
+//! Runs the installed-app flow against its loopback redirect server, playing the browser.
+use std::time::Duration;
+
+use gcal_rs::*;
+
+mod common;
+
+fn token(_: &str, _: usize) -> (u16, String) {
+    (
+        200,
+        r#"{
+            "access_token": "access",
+            "refresh_token": "refresh",
+            "expires_in": 3599,
+            "token_type": "Bearer"
+        }"#
+        .to_string(),
+    )
+}
+
+fn options() -> LoopbackOptions {
+    LoopbackOptions {
+        success_html: "success".to_string(),
+        failure_html: "failure".to_string(),
+        timeout: Duration::from_secs(10),
+        ..Default::default()
+    }
+}
+
+fn query(url: &str, key: &str) -> String {
+    url::Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+        .unwrap()
+}
+
+/// Follow the redirect like a browser would, returning the status and page.
+async fn redirect(url: String) -> (u16, String) {
+    let resp = reqwest::get(url).await.unwrap();
+    (resp.status().as_u16(), resp.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn loopback_exchanges_the_redirected_code() {
+    let (base, requests) = common::serve(token).await;
+    let oauth = common::oauth(&base);
+    let loopback = oauth.loopback(options()).await.unwrap();
+
+    let redirect_url = loopback.redirect_url().to_string();
+    assert!(redirect_url.starts_with("http://127.0.0.1:"));
+    assert_ne!(redirect_url, "http://127.0.0.1:5000/");
+    assert_eq!(query(loopback.auth_url(), "redirect_uri"), redirect_url);
+    let state = query(loopback.auth_url(), "state");
+
+    let browser = tokio::spawn(async move {
+        let favicon = redirect(format!("{}favicon.ico", redirect_url)).await;
+        let page = redirect(format!("{}?code=abc&state={}", redirect_url, state)).await;
+        (favicon, page)
+    });
+    let token = loopback.wait().await.unwrap();
+    let (favicon, page) = browser.await.unwrap();
+
+    assert_eq!(favicon.0, 404);
+    assert_eq!(page, (200, "success".to_string()));
+    assert_eq!(token.access, "access");
+    assert_eq!(token.refresh.as_deref(), Some("refresh"));
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].contains("code=abc"));
+    assert!(requests[0].contains("redirect_uri=http%3A%2F%2F127.0.0.1"));
+    assert!(requests[0].contains("code_verifier="));
+}
+
+#[tokio::test]
+async fn loopback_rejects_a_foreign_state() {
+    let (base, requests) = common::serve(token).await;
+    let oauth = common::oauth(&base);
+    let loopback = oauth.loopback(options()).await.unwrap();
+
+    let url = format!("{}?code=abc&state=forged", loopback.redirect_url());
+    let browser = tokio::spawn(redirect(url));
+    let err = loopback.wait().await.unwrap_err();
+
+    assert_eq!(browser.await.unwrap(), (400, "failure".to_string()));
+    assert!(err.to_string().contains("invalid state"), "{}", err);
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn loopback_reports_denied_access() {
+    let (base, _) = common::serve(token).await;
+    let oauth = common::oauth(&base);
+    let loopback = oauth.loopback(options()).await.unwrap();
+
+    let state = query(loopback.auth_url(), "state");
+    let url = format!(
+        "{}?error=access_denied&state={}",
+        loopback.redirect_url(),
+        state
+    );
+    let browser = tokio::spawn(redirect(url));
+    let err = loopback.wait().await.unwrap_err();
+
+    assert_eq!(browser.await.unwrap(), (400, "failure".to_string()));
+    assert!(err.to_string().contains("denied"), "{}", err);
+}
+
+#[tokio::test]
+async fn loopback_times_out() {
+    let (base, _) = common::serve(token).await;
+    let oauth = common::oauth(&base);
+    let loopback = oauth
+        .loopback(LoopbackOptions {
+            timeout: Duration::from_millis(50),
+            ..options()
+        })
+        .await
+        .unwrap();
+
+    let err = loopback.wait().await.unwrap_err();
+    assert!(err.to_string().contains("Timed out"), "{}", err);
+}
+
+#[tokio::test]
+async fn idle_and_oversized_connections_do_not_block_the_redirect() {
+    use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+    let (base, _) = common::serve(token).await;
+    let oauth = common::oauth(&base);
+    // Shorter than the time a single connection may take to send its request.
+    let loopback = oauth
+        .loopback(LoopbackOptions {
+            timeout: Duration::from_secs(5),
+            ..options()
+        })
+        .await
+        .unwrap();
+
+    let redirect_url = loopback.redirect_url().to_string();
+    let state = query(loopback.auth_url(), "state");
+    let addr = redirect_url
+        .trim_start_matches("http://")
+        .trim_end_matches('/')
+        .to_string();
+    let browser = tokio::spawn(async move {
+        // A speculative preconnect that never sends anything.
+        let idle = TcpStream::connect(&addr).await.unwrap();
+        // A request head that never ends.
+        let mut oversized = TcpStream::connect(&addr).await.unwrap();
+        let header = format!(
+            "GET /?code=big HTTP/1.1\r\nx-filler: {}\r\n",
+            "a".repeat(32 * 1024)
+        );
+        let _ = oversized.write_all(header.as_bytes()).await;
+
+        let page = redirect(format!("{}?code=abc&state={}", redirect_url, state)).await;
+        drop(idle);
+        page
+    });
+    let token = loopback.wait().await.unwrap();
+
+    assert_eq!(browser.await.unwrap(), (200, "success".to_string()));
+    assert_eq!(token.access, "access");
+}
+
+#[tokio::test]
+async fn loopback_can_listen_on_the_configured_redirect_url() {
+    let (base, requests) = common::serve(token).await;
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let configured = format!("http://127.0.0.1:{}/auth", port);
+    let oauth = OAuth::with_endpoints(
+        "client",
+        "secret",
+        &configured,
+        OAuthEndpoints {
+            token_url: format!("{}/token", base),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let loopback = oauth.loopback_on_redirect_url(options()).await.unwrap();
+
+    assert_eq!(loopback.redirect_url(), configured);
+    assert_eq!(query(loopback.auth_url(), "redirect_uri"), configured);
+    let state = query(loopback.auth_url(), "state");
+    let browser = tokio::spawn(redirect(format!("{}?code=abc&state={}", configured, state)));
+    loopback.wait().await.unwrap();
+
+    assert_eq!(browser.await.unwrap(), (200, "success".to_string()));
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].contains(&format!(
+        "redirect_uri=http%3A%2F%2F127.0.0.1%3A{}%2Fauth",
+        port
+    )));
+}
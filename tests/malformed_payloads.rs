@@ -0,0 +1,212 @@
+//! Feeds malformed and unexpected payloads through every client method against a local server.
+//! None of them may panic; broken payloads must surface as `ClientError`s.
+use std::time::Duration;
+
+use futures_util::TryStreamExt;
+use gcal_rs::{
+    types::{EventCalendarDate, EventResponseStatus},
+    *,
+};
+
+mod common;
+
+const MALFORMED: &[(u16, &str)] = &[
+    (200, "{not json"),
+    (200, "<html>oops</html>"),
+    (200, ""),
+    (200, r#"{"items": "nope", "kind": 5}"#),
+    (200, "[1, 2, 3]"),
+    (
+        500,
+        r#"{"error": {"code": 500, "message": "boom", "errors": [{"reason": "backendError"}]}}"#,
+    ),
+    (404, "not found"),
+];
+
+/// Serve the same canned response to every request and return the base URL.
+async fn serve(status: u16, body: &'static str) -> String {
+    common::serve(move |_, _| (status, body.to_string()))
+        .await
+        .0
+}
+
+fn event() -> Event {
+    let mut event = Event::default();
+    event.id = "event".to_string();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+fn invitation() -> Event {
+    let mut event = event();
+    let attendees = serde_json::json!([
+        {"email": "me@example.com", "self": true, "responseStatus": "needsAction"},
+    ]);
+    event.attendees = serde_json::from_value(attendees).unwrap();
+    event
+}
+
+struct Ticket;
+
+impl CalendarBacked for Ticket {
+    fn external_id(&self) -> String {
+        "ticket-1".to_string()
+    }
+
+    fn to_event(&self, _: &mut Event) {}
+
+    fn from_event(_: &Event) -> ClientResult<Self> {
+        Ok(Ticket)
+    }
+}
+
+fn start() -> EventCalendarDate {
+    EventCalendarDate {
+        date_time: Some("2024-01-01T09:00:00Z".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn malformed_payloads_are_errors() {
+    for (status, body) in MALFORMED {
+        let (calendars, events) = common::client(&serve(*status, body).await).clients();
+        let case = format!("{} {:?}", status, body);
+
+        assert!(calendars.list(None).await.is_err(), "calendar list: {}", case);
+        assert!(events.get("primary".into(), "event".into()).await.is_err(), "get: {}", case);
+        assert!(events.list("primary".into(), None).await.is_err(), "list: {}", case);
+        assert!(events.insert(event(), None).await.is_err(), "insert: {}", case);
+        assert!(events.import(event(), None).await.is_err(), "import: {}", case);
+        assert!(events.update(event(), None).await.is_err(), "update: {}", case);
+        assert!(events.patch(event(), None).await.is_err(), "patch: {}", case);
+        assert!(events.add("primary".into(), "Lunch".into(), None).await.is_err(), "add: {}", case);
+        assert!(events.instances(&event(), None).await.is_err(), "instances: {}", case);
+        assert!(events.instances_all(&event(), None).await.is_err(), "instances_all: {}", case);
+        let series = event();
+        let mut stream = Box::pin(events.instances_stream(&series, None));
+        assert!(stream.try_next().await.is_err(), "instances_stream: {}", case);
+        assert!(events.instance(&event(), &start()).await.is_err(), "instance: {}", case);
+        assert!(
+            events.update_instance(&event(), &start(), |_| {}, None).await.is_err(),
+            "update_instance: {}",
+            case
+        );
+        assert!(
+            events.cancel_instance(&event(), &start(), None).await.is_err(),
+            "cancel_instance: {}",
+            case
+        );
+        assert!(
+            events.update_series(&event(), |_| {}, None).await.is_err(),
+            "update_series: {}",
+            case
+        );
+        assert!(
+            events.update_following(&event(), &start(), |_| {}, None).await.is_err(),
+            "update_following: {}",
+            case
+        );
+        assert!(
+            events.respond(&invitation(), EventResponseStatus::Accepted, None, None).await.is_err(),
+            "respond: {}",
+            case
+        );
+        assert!(events.update_attendees(&invitation(), None).await.is_err(), "update_attendees: {}", case);
+        assert!(events.update_attachments(&event(), None).await.is_err(), "update_attachments: {}", case);
+        // The first attempt looks at the event given, the second one fetches it.
+        assert!(
+            matches!(
+                events.await_conference(&event(), Duration::ZERO, 2).await,
+                Err(e) if !matches!(e, ClientError::ConferenceFailed(_))
+            ),
+            "await_conference: {}",
+            case
+        );
+        assert!(
+            events
+                .find_by_external_id("primary".into(), "ticket-1")
+                .await
+                .is_err(),
+            "find_by_external_id: {}",
+            case
+        );
+        assert!(events.load::<Ticket>("primary".into(), "ticket-1").await.is_err(), "load: {}", case);
+        assert!(events.upsert("primary".into(), &Ticket, None).await.is_err(), "upsert: {}", case);
+        assert!(
+            events.delete_by_external_id("primary".into(), "ticket-1", None).await.is_err(),
+            "delete_by_external_id: {}",
+            case
+        );
+
+        // These don't read the body, only error statuses can fail them.
+        let deleted = events.delete(event(), None).await;
+        let moved = events.move_to_calendar(event(), "other".into(), None).await;
+        if *status != 200 {
+            assert!(deleted.is_err(), "delete: {}", case);
+            assert!(moved.is_err(), "move_to_calendar: {}", case);
+        }
+    }
+}
+
+#[tokio::test]
+async fn api_errors_keep_status_and_reason() {
+    let (_, body) = MALFORMED[5];
+    let events = common::client(&serve(500, body).await).event_client();
+
+    match events.get("primary".into(), "event".into()).await {
+        Err(ClientError::ApiError {
+            status,
+            reason,
+            message,
+        }) => {
+            assert_eq!(status, 500);
+            assert_eq!(reason.as_deref(), Some("backendError"));
+            assert_eq!(message, "boom");
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn invalid_token_is_reported() {
+    let events = common::client(&serve(401, "{}").await).event_client();
+
+    assert!(matches!(
+        events.get("primary".into(), "event".into()).await,
+        Err(ClientError::InvalidToken)
+    ));
+}
+
+#[tokio::test]
+async fn unexpected_values_are_tolerated() {
+    let body = r#"{
+        "accessRole": "superOwner",
+        "unknownField": {"nested": true},
+        "items": [{
+            "id": "event",
+            "eventType": "somethingNew",
+            "status": "rescheduled",
+            "attendees": [{"email": "a@example.com", "responseStatus": "maybe"}]
+        }]
+    }"#;
+    let events = common::client(&serve(200, body).await).event_client();
+
+    let list = events.list("primary".into(), None).await.unwrap();
+    assert_eq!(list[0].calendar_id, "primary");
+    assert_eq!(list[0].event_type, types::EventType::Unknown("somethingNew".into()));
+}
+
+#[test]
+fn access_roles_parse_strictly() {
+    assert_eq!("reader".parse::<MinAccessRole>(), Ok(MinAccessRole::Reader));
+    assert_eq!(
+        "freeBusyReader".parse::<CalendarAccessRole>(),
+        Ok(CalendarAccessRole::FreeBusyReader)
+    );
+    assert_eq!(
+        "admin".parse::<MinAccessRole>(),
+        Err(ParseAccessRoleError("admin".to_string()))
+    );
+    assert!("".parse::<CalendarAccessRole>().is_err());
+}
@@ -0,0 +1,229 @@
+//! Previews what quickAdd will create from a piece of text.
+use chrono::{DateTime, TimeZone};
+use chrono_tz::{Europe::Berlin, Tz};
+
+use gcal_rs::*;
+
+mod common;
+
+/// Wednesday, 6 March 2024, 10:00 in Berlin.
+fn now() -> DateTime<Tz> {
+    Berlin.with_ymd_and_hms(2024, 3, 6, 10, 0, 0).unwrap()
+}
+
+fn local(dt: &DateTime<Tz>) -> String {
+    dt.format("%Y-%m-%d %H:%M").to_string()
+}
+
+#[test]
+fn phrases_are_understood() {
+    // text, summary, location, start, end, all day
+    let cases = [
+        (
+            "Lunch with Sam tomorrow at noon",
+            "Lunch with Sam",
+            None,
+            "2024-03-07 12:00",
+            "2024-03-07 13:00",
+            false,
+        ),
+        (
+            "Lunch at Cafe Roma tomorrow at noon",
+            "Lunch",
+            Some("Cafe Roma"),
+            "2024-03-07 12:00",
+            "2024-03-07 13:00",
+            false,
+        ),
+        (
+            "Review friday at 3pm",
+            "Review",
+            None,
+            "2024-03-08 15:00",
+            "2024-03-08 16:00",
+            false,
+        ),
+        (
+            "Gym next wed",
+            "Gym",
+            None,
+            "2024-03-13 00:00",
+            "2024-03-14 00:00",
+            true,
+        ),
+        (
+            "Dentist on wednesday",
+            "Dentist",
+            None,
+            "2024-03-06 00:00",
+            "2024-03-07 00:00",
+            true,
+        ),
+        (
+            "Call at 3pm for 2 hours",
+            "Call",
+            None,
+            "2024-03-06 15:00",
+            "2024-03-06 17:00",
+            false,
+        ),
+        (
+            "Call at 3pm for 30 min",
+            "Call",
+            None,
+            "2024-03-06 15:00",
+            "2024-03-06 15:30",
+            false,
+        ),
+        (
+            "Party at 7",
+            "Party",
+            None,
+            "2024-03-06 19:00",
+            "2024-03-06 20:00",
+            false,
+        ),
+        (
+            "Standup mon 9:30-9:45am",
+            "Standup",
+            None,
+            "2024-03-11 09:30",
+            "2024-03-11 09:45",
+            false,
+        ),
+        (
+            "Deploy 2pm until 4",
+            "Deploy",
+            None,
+            "2024-03-06 14:00",
+            "2024-03-06 16:00",
+            false,
+        ),
+        (
+            "Night shift at 10pm to 6am",
+            "Night shift",
+            None,
+            "2024-03-06 22:00",
+            "2024-03-07 06:00",
+            false,
+        ),
+        (
+            "Trip for 3 days",
+            "Trip",
+            None,
+            "2024-03-06 00:00",
+            "2024-03-09 00:00",
+            true,
+        ),
+        (
+            "Holiday jan 5",
+            "Holiday",
+            None,
+            "2025-01-05 00:00",
+            "2025-01-06 00:00",
+            true,
+        ),
+        (
+            "Launch 2024-04-01",
+            "Launch",
+            None,
+            "2024-04-01 00:00",
+            "2024-04-02 00:00",
+            true,
+        ),
+        (
+            "Launch 5th of april 2025",
+            "Launch",
+            None,
+            "2025-04-05 00:00",
+            "2025-04-06 00:00",
+            true,
+        ),
+        (
+            "Offsite 3/20",
+            "Offsite",
+            None,
+            "2024-03-20 00:00",
+            "2024-03-21 00:00",
+            true,
+        ),
+    ];
+    for (text, summary, location, start, end, all_day) in cases {
+        let preview = QuickAddPreview::parse(text, &now()).unwrap();
+        assert_eq!(preview.summary, summary, "{}", text);
+        assert_eq!(preview.location.as_deref(), location, "{}", text);
+        assert_eq!(local(&preview.start), start, "{}", text);
+        assert_eq!(local(&preview.end), end, "{}", text);
+        assert_eq!(preview.all_day, all_day, "{}", text);
+    }
+}
+
+#[test]
+fn all_day_previews_become_date_events() {
+    let event = QuickAddPreview::parse("Trip tomorrow", &now())
+        .unwrap()
+        .to_event()
+        .unwrap();
+    assert_eq!(event.summary, "Trip");
+    assert_eq!(event.start.date.as_deref(), Some("2024-03-07"));
+    assert_eq!(event.end.date.as_deref(), Some("2024-03-08"));
+    assert!(event.start.date_time.is_none());
+
+    let event = QuickAddPreview::parse("Call at 3pm", &now())
+        .unwrap()
+        .to_event()
+        .unwrap();
+    assert!(event.start.date.is_none());
+    assert_eq!(event.start.time_zone.as_deref(), Some("Europe/Berlin"));
+}
+
+#[tokio::test]
+async fn previews_only_send_what_they_describe() {
+    let (base, requests) = common::serve(|_, _| (200, r#"{"id": "event"}"#.to_string())).await;
+    let events = common::client(&base).event_client();
+
+    let mut event = QuickAddPreview::parse("Lunch at Cafe Roma tomorrow at noon", &now())
+        .unwrap()
+        .to_event()
+        .unwrap();
+    event.calendar_id = "primary".to_string();
+    events.insert(event, None).await.unwrap();
+
+    assert_eq!(
+        common::body(&requests.lock().unwrap()[0]),
+        serde_json::json!({
+            "summary": "Lunch",
+            "location": "Cafe Roma",
+            "start": {"dateTime": "2024-03-07T12:00:00+01:00", "timeZone": "Europe/Berlin"},
+            "end": {"dateTime": "2024-03-07T13:00:00+01:00", "timeZone": "Europe/Berlin"},
+        })
+    );
+}
+
+#[test]
+fn amounts_that_are_not_durations_stay_in_the_summary() {
+    for text in [
+        "Meeting at 3pm for inf hours",
+        "Meeting at 3pm for NaN hours",
+        "Meeting at 3pm for -2 hours",
+        "Meeting at 3pm for 0 hours",
+        "Meeting at 3pm for 1e300 days",
+        "Meeting at 3pm for 9999999999999 hours",
+    ] {
+        let preview = QuickAddPreview::parse(text, &now()).unwrap();
+        assert!(preview.summary.starts_with("Meeting for "), "{}", text);
+        assert_eq!(local(&preview.end), "2024-03-06 16:00", "{}", text);
+    }
+}
+
+#[test]
+fn events_beyond_the_calendar_are_rejected() {
+    assert_eq!(
+        QuickAddPreview::parse("Trip for 99999999999 days", &now()),
+        None
+    );
+    assert_eq!(
+        QuickAddPreview::parse("Meeting at 3pm for 99999999999 days", &now()),
+        None
+    );
+}
@@ -0,0 +1,334 @@
+//! Expands recurrence rules locally and checks them against RFC 5545.
+use chrono::{DateTime, NaiveDate, TimeZone, Weekday};
+use chrono_tz::{America::New_York, Tz, UTC};
+
+use gcal_rs::{types::EventCalendarDate, *};
+
+fn at(tz: Tz, y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Tz> {
+    tz.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+}
+
+/// Expand a single rule from `dtstart` until the year 2100.
+fn expand(rule: &str, dtstart: DateTime<Tz>) -> Vec<String> {
+    let rule: RecurrenceRule = rule.parse().unwrap();
+    rule.occurrences(&dtstart, &at(dtstart.timezone(), 2100, 1, 1, 0, 0))
+        .into_iter()
+        .map(|o| o.to_rfc3339())
+        .collect()
+}
+
+fn dates(rule: &str, dtstart: DateTime<Tz>) -> Vec<String> {
+    let rule: RecurrenceRule = rule.parse().unwrap();
+    rule.occurrences(&dtstart, &at(dtstart.timezone(), 2100, 1, 1, 0, 0))
+        .into_iter()
+        .map(|o| o.date_naive().to_string())
+        .collect()
+}
+
+#[test]
+fn daily_keeps_wall_clock_time_across_dst() {
+    assert_eq!(
+        expand("FREQ=DAILY;COUNT=3", at(New_York, 2024, 3, 9, 9, 0)),
+        [
+            "2024-03-09T09:00:00-05:00",
+            "2024-03-10T09:00:00-04:00",
+            "2024-03-11T09:00:00-04:00",
+        ]
+    );
+}
+
+#[test]
+fn weekly_keeps_wall_clock_time_across_dst() {
+    assert_eq!(
+        expand(
+            "FREQ=WEEKLY;BYDAY=MO;COUNT=2",
+            at(New_York, 2024, 10, 28, 9, 0)
+        ),
+        ["2024-10-28T09:00:00-04:00", "2024-11-04T09:00:00-05:00"]
+    );
+}
+
+#[test]
+fn times_in_a_dst_gap_move_forward() {
+    assert_eq!(
+        expand("FREQ=DAILY;COUNT=3", at(New_York, 2024, 3, 9, 2, 30)),
+        [
+            "2024-03-09T02:30:00-05:00",
+            "2024-03-10T03:30:00-04:00",
+            "2024-03-11T02:30:00-04:00",
+        ]
+    );
+}
+
+#[test]
+fn by_day_with_negative_ordinal() {
+    assert_eq!(
+        dates(
+            "FREQ=MONTHLY;BYDAY=-1FR;COUNT=4",
+            at(UTC, 2024, 1, 26, 9, 0)
+        ),
+        ["2024-01-26", "2024-02-23", "2024-03-29", "2024-04-26"]
+    );
+    assert_eq!(
+        RecurrenceRule::monthly_by_weekday(-1, Weekday::Fri).to_string(),
+        "RRULE:FREQ=MONTHLY;BYDAY=-1FR"
+    );
+}
+
+#[test]
+fn by_month_day_31_skips_short_months() {
+    assert_eq!(
+        dates(
+            "FREQ=MONTHLY;BYMONTHDAY=31;COUNT=4",
+            at(UTC, 2024, 1, 31, 9, 0)
+        ),
+        ["2024-01-31", "2024-03-31", "2024-05-31", "2024-07-31"]
+    );
+}
+
+#[test]
+fn by_set_pos_picks_the_last_weekday() {
+    assert_eq!(
+        dates(
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=3",
+            at(UTC, 2024, 1, 31, 9, 0)
+        ),
+        ["2024-01-31", "2024-02-29", "2024-03-29"]
+    );
+}
+
+#[test]
+fn until_is_inclusive_and_matches_count() {
+    let start = at(UTC, 2024, 1, 1, 9, 0);
+    let until = expand("FREQ=DAILY;UNTIL=20240105T090000Z", start);
+    assert_eq!(until.len(), 5);
+    assert_eq!(until, expand("FREQ=DAILY;COUNT=5", start));
+    assert_eq!(expand("FREQ=DAILY;UNTIL=20240105T085959Z", start).len(), 4);
+    assert!("FREQ=DAILY;COUNT=5;UNTIL=20240105T090000Z"
+        .parse::<RecurrenceRule>()
+        .is_err());
+}
+
+#[test]
+fn count_zero_is_rejected() {
+    assert!("FREQ=DAILY;COUNT=0".parse::<RecurrenceRule>().is_err());
+    assert!(RecurrenceRule::daily().with_count(0).is_err());
+    assert_eq!(
+        RecurrenceRule::daily().with_count(2).unwrap().to_string(),
+        "RRULE:FREQ=DAILY;COUNT=2"
+    );
+}
+
+#[test]
+fn exdates_are_removed_and_rdates_added() {
+    let recurrence = Recurrence::parse([
+        "RRULE:FREQ=DAILY;COUNT=4",
+        "EXDATE;TZID=America/New_York:20240102T090000",
+        "RDATE;TZID=America/New_York:20240110T090000",
+        "RDATE;VALUE=DATE:20240112",
+    ])
+    .unwrap();
+    let start = at(New_York, 2024, 1, 1, 9, 0);
+    let occurrences: Vec<String> = recurrence
+        .between(&start, &start, &at(New_York, 2024, 2, 1, 0, 0))
+        .into_iter()
+        .map(|o| o.to_rfc3339())
+        .collect();
+    assert_eq!(
+        occurrences,
+        [
+            "2024-01-01T09:00:00-05:00",
+            "2024-01-03T09:00:00-05:00",
+            "2024-01-04T09:00:00-05:00",
+            "2024-01-10T09:00:00-05:00",
+            "2024-01-12T09:00:00-05:00",
+        ]
+    );
+}
+
+#[test]
+fn by_week_no_uses_the_week_numbering_year() {
+    // Week 1 of 2025 starts on Monday 2024-12-30, week 1 of 2026 on 2025-12-29.
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;BYWEEKNO=1;BYDAY=MO;COUNT=3",
+            at(UTC, 2024, 1, 1, 9, 0)
+        ),
+        ["2024-01-01", "2024-12-30", "2025-12-29"]
+    );
+    // Every other week-numbering year: 2024 and 2026, whose week 1 starts in 2025.
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;INTERVAL=2;BYWEEKNO=1;BYDAY=MO;COUNT=2",
+            at(UTC, 2024, 1, 1, 9, 0)
+        ),
+        ["2024-01-01", "2025-12-29"]
+    );
+    // Week 53 of 2020 ends on 2021-01-03; 2026 is the next year with 53 weeks.
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;BYWEEKNO=53;BYDAY=FR;COUNT=2",
+            at(UTC, 2021, 1, 1, 9, 0)
+        ),
+        ["2021-01-01", "2027-01-01"]
+    );
+    assert_eq!(
+        dates(
+            "FREQ=YEARLY;BYWEEKNO=-1;BYDAY=MO;COUNT=2",
+            at(UTC, 2020, 12, 28, 9, 0)
+        ),
+        ["2020-12-28", "2021-12-27"]
+    );
+}
+
+#[test]
+fn all_day_occurrences_end_at_local_midnight_across_dst() {
+    let day = |d| EventCalendarDate::from_date(NaiveDate::from_ymd_opt(2024, 3, d).unwrap());
+    let mut event = Event::default();
+    event.start = day(9).with_time_zone("America/New_York");
+    event.end = day(10).with_time_zone("America/New_York");
+    event.recurrence = vec!["RRULE:FREQ=DAILY;COUNT=3".to_string()];
+    let occurrences = event
+        .occurrences(
+            &at(New_York, 2024, 3, 1, 0, 0),
+            &at(New_York, 2024, 4, 1, 0, 0),
+        )
+        .unwrap();
+    let ends: Vec<String> = occurrences.iter().map(|o| o.end.to_rfc3339()).collect();
+    assert_eq!(
+        ends,
+        [
+            "2024-03-10T00:00:00-05:00",
+            "2024-03-11T00:00:00-04:00",
+            "2024-03-12T00:00:00-04:00",
+        ]
+    );
+    assert!(occurrences.iter().all(|o| o.all_day));
+}
+
+#[test]
+fn recurrence_lines_keep_their_order_and_duplicates() {
+    let lines = [
+        "RDATE:20240110T090000Z",
+        "RRULE:FREQ=DAILY;COUNT=3",
+        "EXDATE:20240102T090000Z",
+        "RDATE:20240110T090000Z",
+    ];
+    let event: Event = serde_json::from_value(serde_json::json!({
+        "id": "series",
+        "recurrence": lines,
+    }))
+    .unwrap();
+    assert_eq!(event.recurrence, lines);
+    assert_eq!(
+        serde_json::to_value(&event).unwrap()["recurrence"],
+        serde_json::json!(lines)
+    );
+}
+
+#[test]
+fn typed_accessors_read_each_kind_of_line() {
+    let mut event = Event::default();
+    event.recurrence = vec![
+        "RRULE:FREQ=WEEKLY;BYDAY=MO".to_string(),
+        "EXDATE;TZID=America/New_York:20240108T090000".to_string(),
+        "RRULE:FREQ=MONTHLY;BYDAY=-1FR".to_string(),
+        "RDATE;VALUE=DATE:20240120,20240121".to_string(),
+        "EXDATE:20240115T140000Z".to_string(),
+    ];
+
+    assert_eq!(
+        event.rrules().unwrap(),
+        [
+            RecurrenceRule::weekly_on(&[Weekday::Mon]),
+            RecurrenceRule::monthly_by_weekday(-1, Weekday::Fri),
+        ]
+    );
+    assert_eq!(
+        event.exdates().unwrap(),
+        [
+            RecurrenceDate::Zoned(at(New_York, 2024, 1, 8, 9, 0)),
+            RecurrenceDate::Utc(at(UTC, 2024, 1, 15, 14, 0).to_utc()),
+        ]
+    );
+    assert_eq!(
+        event.rdates().unwrap(),
+        [
+            RecurrenceDate::Date(NaiveDate::from_ymd_opt(2024, 1, 20).unwrap()),
+            RecurrenceDate::Date(NaiveDate::from_ymd_opt(2024, 1, 21).unwrap()),
+        ]
+    );
+
+    event.recurrence.push("RRULE:FREQ=SOMETIMES".to_string());
+    assert!(matches!(
+        event.rrules(),
+        Err(ClientError::InvalidRecurrence(_))
+    ));
+}
+
+#[test]
+fn lines_are_appended_in_order() {
+    let mut event = Event::default();
+    event.add_rrule(
+        RecurrenceRule::weekly_on(&[Weekday::Mon, Weekday::Wed])
+            .with_count(4)
+            .unwrap(),
+    );
+    event.add_exdate(RecurrenceDate::Zoned(at(New_York, 2024, 1, 8, 9, 0)));
+    event.add_rdate(RecurrenceDate::Date(
+        NaiveDate::from_ymd_opt(2024, 1, 20).unwrap(),
+    ));
+    event.add_exdate(RecurrenceDate::Utc(at(UTC, 2024, 1, 10, 14, 0).to_utc()));
+    event.add_exdate(RecurrenceDate::Utc(at(UTC, 2024, 1, 10, 14, 0).to_utc()));
+
+    assert_eq!(
+        event.recurrence,
+        [
+            "RRULE:FREQ=WEEKLY;COUNT=4;BYDAY=MO,WE",
+            "EXDATE;TZID=America/New_York:20240108T090000",
+            "RDATE;VALUE=DATE:20240120",
+            "EXDATE:20240110T140000Z",
+            "EXDATE:20240110T140000Z",
+        ]
+    );
+    assert_eq!(event.exdates().unwrap().len(), 3);
+}
+
+#[test]
+fn weekly_on_repeats_on_the_given_days() {
+    let rule = RecurrenceRule::weekly_on(&[Weekday::Tue, Weekday::Thu]);
+    assert_eq!(rule.to_string(), "RRULE:FREQ=WEEKLY;BYDAY=TU,TH");
+    assert_eq!(rule.to_string().parse::<RecurrenceRule>().unwrap(), rule);
+
+    let start = at(UTC, 2024, 1, 2, 9, 0);
+    assert_eq!(
+        rule.occurrences(&start, &at(UTC, 2024, 1, 12, 0, 0))
+            .into_iter()
+            .map(|o| o.date_naive().to_string())
+            .collect::<Vec<_>>(),
+        ["2024-01-02", "2024-01-04", "2024-01-09", "2024-01-11"]
+    );
+}
+
+#[test]
+fn with_until_replaces_the_count() {
+    let until = RecurrenceDate::Utc(at(UTC, 2024, 1, 4, 9, 0).to_utc());
+    let rule = RecurrenceRule::daily()
+        .with_count(10)
+        .unwrap()
+        .with_until(until);
+    assert_eq!(rule.count, None);
+    assert_eq!(rule.until, Some(until));
+    assert_eq!(rule.to_string(), "RRULE:FREQ=DAILY;UNTIL=20240104T090000Z");
+    assert_eq!(
+        rule.occurrences(&at(UTC, 2024, 1, 1, 9, 0), &at(UTC, 2100, 1, 1, 0, 0))
+            .len(),
+        4
+    );
+
+    // COUNT replaces UNTIL the other way round.
+    assert_eq!(
+        rule.with_count(2).unwrap().to_string(),
+        "RRULE:FREQ=DAILY;COUNT=2"
+    );
+}
@@ -0,0 +1,201 @@
+//! Splits recurring series for "this and following events" edits.
+use gcal_rs::{types::EventCalendarDate, *};
+
+mod common;
+
+fn series(recurrence: &[&str]) -> Event {
+    let mut event: Event = serde_json::from_value(serde_json::json!({
+        "id": "series",
+        "summary": "Standup",
+        "start": {"dateTime": "2024-01-01T09:00:00Z"},
+        "end": {"dateTime": "2024-01-01T09:30:00Z"},
+        "recurrence": recurrence,
+    }))
+    .unwrap();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+fn at(date_time: &str) -> EventCalendarDate {
+    EventCalendarDate {
+        date_time: Some(date_time.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Create the following series as `next` and accept the truncated one.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("POST ") {
+        (200, r#"{"id": "next"}"#.to_string())
+    } else {
+        (200, r#"{"id": "series"}"#.to_string())
+    }
+}
+
+/// Like `respond`, but fail to truncate the series.
+fn failing_update(request: &str, seen: usize) -> (u16, String) {
+    if request.starts_with("PUT ") {
+        (
+            503,
+            r#"{"error": {"code": 503, "message": "unavailable"}}"#.to_string(),
+        )
+    } else {
+        respond(request, seen)
+    }
+}
+
+#[test]
+fn split_carries_over_the_remaining_count() {
+    let (previous, following) = series(&["RRULE:FREQ=WEEKLY;COUNT=3"])
+        .split_recurrence(&at("2024-01-08T09:00:00Z"))
+        .unwrap();
+    assert_eq!(previous, ["RRULE:FREQ=WEEKLY;UNTIL=20240108T085959Z"]);
+    assert_eq!(following, ["RRULE:FREQ=WEEKLY;COUNT=2"]);
+
+    let (_, following) = series(&["RRULE:FREQ=WEEKLY;COUNT=3"])
+        .split_recurrence(&at("2024-01-15T09:00:00Z"))
+        .unwrap();
+    assert_eq!(following, ["RRULE:FREQ=WEEKLY;COUNT=1"]);
+}
+
+#[test]
+fn split_after_the_last_occurrence_is_an_error() {
+    let event = series(&["RRULE:FREQ=WEEKLY;COUNT=2"]);
+    assert!(matches!(
+        event.split_recurrence(&at("2024-01-15T09:00:00Z")),
+        Err(ClientError::InvalidRecurrence(_))
+    ));
+
+    let event = series(&["RRULE:FREQ=DAILY;UNTIL=20240103T090000Z"]);
+    assert!(event.split_recurrence(&at("2024-01-04T09:00:00Z")).is_err());
+}
+
+#[test]
+fn split_partitions_exdates_and_rdates() {
+    let event = series(&[
+        "RRULE:FREQ=DAILY",
+        "EXDATE:20240102T090000Z,20240110T090000Z",
+        "RDATE:20240103T120000Z",
+        "RDATE:20240112T120000Z",
+    ]);
+    let (previous, following) = event.split_recurrence(&at("2024-01-05T09:00:00Z")).unwrap();
+    assert_eq!(
+        previous,
+        [
+            "RRULE:FREQ=DAILY;UNTIL=20240105T085959Z",
+            "EXDATE:20240102T090000Z",
+            "RDATE:20240103T120000Z",
+        ]
+    );
+    assert_eq!(
+        following,
+        [
+            "RRULE:FREQ=DAILY",
+            "EXDATE:20240110T090000Z",
+            "RDATE:20240112T120000Z",
+        ]
+    );
+}
+
+#[test]
+fn split_keeps_rules_that_end_before_it() {
+    let event = series(&["RRULE:FREQ=DAILY;COUNT=3", "RDATE:20240110T090000Z"]);
+    let (previous, following) = event.split_recurrence(&at("2024-01-10T09:00:00Z")).unwrap();
+    assert_eq!(previous, ["RRULE:FREQ=DAILY;COUNT=3"]);
+    assert_eq!(following, ["RDATE:20240110T090000Z"]);
+
+    let event = series(&[
+        "RRULE:FREQ=DAILY;UNTIL=20240103T090000Z",
+        "RDATE:20240110T090000Z",
+    ]);
+    let (previous, _) = event.split_recurrence(&at("2024-01-10T09:00:00Z")).unwrap();
+    assert_eq!(previous, ["RRULE:FREQ=DAILY;UNTIL=20240103T090000Z"]);
+}
+
+#[tokio::test]
+async fn update_following_creates_the_new_series_first() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let (previous, next) = events
+        .update_following(
+            &series(&["RRULE:FREQ=DAILY;COUNT=10"]),
+            &at("2024-01-05T09:00:00Z"),
+            |e| e.summary = "Later standup".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(previous.unwrap().id, "series");
+    assert_eq!(next.id, "next");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].starts_with("POST /calendars/primary/events"));
+    assert!(requests[0].contains(r#""summary":"Later standup""#));
+    assert!(requests[0].contains("COUNT=6"));
+    assert!(requests[1].starts_with("PUT /calendars/primary/events/series"));
+    assert!(requests[1].contains("UNTIL=20240105T085959Z"));
+}
+
+#[tokio::test]
+async fn update_following_starts_a_fresh_series() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    let mut event = series(&["RRULE:FREQ=DAILY;COUNT=10"]);
+    event.set_private_property(EXTERNAL_ID_PROPERTY, "T-1");
+    event.set_private_property("team", "core");
+    event.request_conference(types::EventConferenceSolutionKeyType::HangoutsMeet);
+    event.attendees = serde_json::from_value(serde_json::json!([
+        {"email": "sam@example.com", "responseStatus": "accepted"},
+        {"email": "kim@example.com", "responseStatus": "declined"},
+    ]))
+    .unwrap();
+    events
+        .update_following(&event, &at("2024-01-05T09:00:00Z"), |_| {}, None)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let body = common::body(&requests[0]);
+    assert_eq!(
+        body["extendedProperties"],
+        serde_json::json!({"private": {"team": "core"}})
+    );
+    assert!(body.get("conferenceData").is_none());
+    for attendee in body["attendees"].as_array().unwrap() {
+        assert_eq!(attendee["responseStatus"], "needsAction");
+    }
+
+    // The truncated series keeps everything.
+    let body = common::body(&requests[1]);
+    assert_eq!(body["extendedProperties"]["private"]["externalId"], "T-1");
+    assert!(body.get("conferenceData").is_some());
+    assert_eq!(body["attendees"][0]["responseStatus"], "accepted");
+}
+
+#[tokio::test]
+async fn update_following_removes_the_new_series_when_truncating_fails() {
+    let (base, requests) = common::serve(failing_update).await;
+    let events = common::client(&base).event_client();
+
+    let result = events
+        .update_following(
+            &series(&["RRULE:FREQ=DAILY;COUNT=10"]),
+            &at("2024-01-05T09:00:00Z"),
+            |_| {},
+            None,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientError::ApiError { status: 503, .. })
+    ));
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].starts_with("POST /calendars/primary/events"));
+    assert!(requests[1].starts_with("PUT /calendars/primary/events/series"));
+    assert!(requests[2].starts_with("DELETE /calendars/primary/events/next"));
+}
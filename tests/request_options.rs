@@ -0,0 +1,161 @@
+//! Request options reach the query string of every mutating request.
+use gcal_rs::{
+    types::{EventCalendarDate, SendUpdates},
+    *,
+};
+
+mod common;
+
+fn options() -> EventRequestOptions {
+    EventRequestOptions {
+        send_updates: Some(SendUpdates::ExternalOnly),
+        conference_data_version: Some(1),
+        supports_attachments: Some(true),
+        max_attendees: Some(5),
+    }
+}
+
+fn assert_options(request: &str) {
+    let query = common::query(request);
+    assert_eq!(
+        common::param(&query, "sendUpdates").as_deref(),
+        Some("externalOnly"),
+        "{}",
+        request
+    );
+    assert_eq!(
+        common::param(&query, "conferenceDataVersion").as_deref(),
+        Some("1")
+    );
+    assert_eq!(
+        common::param(&query, "supportsAttachments").as_deref(),
+        Some("true")
+    );
+    assert_eq!(common::param(&query, "maxAttendees").as_deref(), Some("5"));
+}
+
+fn series() -> Event {
+    let mut event: Event = serde_json::from_value(serde_json::json!({
+        "id": "series",
+        "start": {"dateTime": "2024-01-01T09:00:00Z"},
+        "end": {"dateTime": "2024-01-01T09:30:00Z"},
+        "recurrence": ["RRULE:FREQ=DAILY;COUNT=10"],
+    }))
+    .unwrap();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+fn at(date_time: &str) -> EventCalendarDate {
+    EventCalendarDate {
+        date_time: Some(date_time.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Every lookup finds the instance `series_20240105`, every write succeeds.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("GET ") {
+        return (
+            200,
+            r#"{"items": [{
+                "id": "series_20240105",
+                "recurringEventId": "series",
+                "extendedProperties": {"private": {"externalId": "T-1"}}
+            }]}"#
+                .to_string(),
+        );
+    }
+    if request.starts_with("DELETE ") {
+        return (204, String::new());
+    }
+    (200, r#"{"id": "series"}"#.to_string())
+}
+
+#[tokio::test]
+async fn recurring_edits_pass_the_options_on() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    events
+        .update_instance(
+            &series(),
+            &at("2024-01-05T09:00:00Z"),
+            |_| {},
+            Some(options()),
+        )
+        .await
+        .unwrap();
+    events
+        .cancel_instance(&series(), &at("2024-01-05T09:00:00Z"), Some(options()))
+        .await
+        .unwrap();
+    events
+        .update_series(&series(), |_| {}, Some(options()))
+        .await
+        .unwrap();
+    events
+        .update_following(
+            &series(),
+            &at("2024-01-05T09:00:00Z"),
+            |_| {},
+            Some(options()),
+        )
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let writes: Vec<_> = requests.iter().filter(|r| !r.starts_with("GET ")).collect();
+    assert_eq!(writes.len(), 5);
+    for request in writes {
+        assert_options(request);
+    }
+}
+
+#[tokio::test]
+async fn calendar_backed_writes_pass_the_options_on() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    struct Ticket;
+    impl CalendarBacked for Ticket {
+        fn external_id(&self) -> String {
+            "T-1".to_string()
+        }
+        fn to_event(&self, _: &mut Event) {}
+        fn from_event(_: &Event) -> ClientResult<Self> {
+            Ok(Ticket)
+        }
+    }
+
+    events
+        .upsert("primary".into(), &Ticket, Some(options()))
+        .await
+        .unwrap();
+    assert!(events
+        .delete_by_external_id("primary".into(), "T-1", Some(options()))
+        .await
+        .unwrap());
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 4);
+    assert!(requests[1].starts_with("PUT "));
+    assert_options(&requests[1]);
+    assert!(requests[3].starts_with("DELETE "));
+    assert_options(&requests[3]);
+}
+
+#[tokio::test]
+async fn no_options_add_no_parameters() {
+    let (base, requests) = common::serve(respond).await;
+    let events = common::client(&base).event_client();
+
+    events
+        .cancel_instance(&series(), &at("2024-01-05T09:00:00Z"), None)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[1].starts_with("DELETE "));
+    assert!(common::query(&requests[1]).is_empty());
+}
@@ -0,0 +1,158 @@
+//! Revokes tokens and signs out against a local revocation endpoint.
+use std::sync::Arc;
+
+use gcal_rs::*;
+
+mod common;
+
+/// Accept refresh tokens, report everything else as already revoked.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.contains("token=refresh") || request.contains("token_type_hint=access_token") {
+        (200, String::new())
+    } else {
+        (
+            400,
+            r#"{"error": "invalid_token", "error_description": "Token expired or revoked"}"#
+                .to_string(),
+        )
+    }
+}
+
+fn unavailable(_: &str, _: usize) -> (u16, String) {
+    (503, r#"{"error": "temporarily_unavailable"}"#.to_string())
+}
+
+fn token(refresh: Option<&str>) -> OToken {
+    OToken {
+        access: "access".to_string(),
+        refresh: refresh.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn revoke_prefers_the_refresh_token() {
+    let (base, requests) = common::serve(respond).await;
+    let oauth = Arc::new(common::oauth(&base));
+
+    oauth.revoke(&token(Some("refresh"))).await.unwrap();
+    oauth.revoke(&token(None)).await.unwrap();
+    // Google answers invalid_token for tokens revoked before.
+    oauth.revoke(&token(Some("revoked"))).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("POST /revoke"));
+    assert!(requests[0].contains("token=refresh&token_type_hint=refresh_token"));
+    assert!(requests[1].contains("token=access&token_type_hint=access_token"));
+    assert_eq!(requests.len(), 3);
+}
+
+#[tokio::test]
+async fn revoke_uses_the_shared_http_client() {
+    let (base, requests) = common::serve(respond).await;
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-shared", reqwest::header::HeaderValue::from_static("1"));
+    let http = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+
+    let oauth = common::oauth(&base).with_http_client(http);
+    oauth.revoke(&token(Some("refresh"))).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].to_lowercase().contains("x-shared: 1"));
+}
+
+#[tokio::test]
+async fn sign_out_clears_the_store_even_when_revoking_fails() {
+    let (base, _) = common::serve(unavailable).await;
+    let store = MemoryTokenStore::with_token(token(Some("refresh")));
+    let err = Arc::new(common::oauth(&base))
+        .sign_out(&store)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("temporarily_unavailable"),
+        "{}",
+        err
+    );
+    assert_eq!(store.load().unwrap(), None);
+
+    let (base, requests) = common::serve(respond).await;
+    store.save(&token(Some("refresh"))).unwrap();
+    Arc::new(common::oauth(&base))
+        .sign_out(&store)
+        .await
+        .unwrap();
+    assert_eq!(store.load().unwrap(), None);
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    // Nothing left to revoke.
+    Arc::new(common::oauth(&base))
+        .sign_out(&store)
+        .await
+        .unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn client_sign_out_forgets_the_token() {
+    let (base, requests) = common::serve(respond).await;
+    let store = Arc::new(MemoryTokenStore::with_token(token(Some("refresh"))));
+    let client = GCalClient::builder(token(Some("refresh")))
+        .oauth(Arc::new(common::oauth(&base)))
+        .token_store(store.clone())
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    client.sign_out().await.unwrap();
+    assert_eq!(store.load().unwrap(), None);
+    assert_eq!(client.token().await, OToken::default());
+    assert!(requests.lock().unwrap()[0].contains("token=refresh"));
+
+    let without_oauth = GCalClient::builder(token(None)).build().unwrap();
+    assert!(matches!(
+        without_oauth.sign_out().await,
+        Err(ClientError::AuthError(_))
+    ));
+}
+
+#[tokio::test]
+async fn client_sign_out_forgets_the_token_when_revoking_fails() {
+    let (base, requests) = common::serve(unavailable).await;
+    let store = Arc::new(MemoryTokenStore::with_token(token(Some("refresh"))));
+    let client = GCalClient::builder(token(Some("refresh")))
+        .oauth(Arc::new(common::oauth(&base)))
+        .token_store(store.clone())
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    let err = client.sign_out().await.unwrap_err();
+    assert!(
+        err.to_string().contains("temporarily_unavailable"),
+        "{}",
+        err
+    );
+    assert_eq!(store.load().unwrap(), None);
+    assert_eq!(client.token().await, OToken::default());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn client_forget_clears_without_revoking() {
+    let (base, requests) = common::serve(respond).await;
+    let store = Arc::new(MemoryTokenStore::with_token(token(Some("refresh"))));
+    let client = GCalClient::builder(token(Some("refresh")))
+        .token_store(store.clone())
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    client.forget().await.unwrap();
+    assert_eq!(store.load().unwrap(), None);
+    assert_eq!(client.token().await, OToken::default());
+    assert!(requests.lock().unwrap().is_empty());
+}
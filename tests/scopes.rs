@@ -0,0 +1,135 @@
+//! Checks granted scopes before sending requests.
+use std::sync::Arc;
+
+use gcal_rs::*;
+
+mod common;
+
+fn respond(_: &str, _: usize) -> (u16, String) {
+    (200, r#"{"id": "event", "etag": "1", "items": []}"#.to_string())
+}
+
+fn client(base: &str, scopes: Option<CalendarScopes>) -> Arc<GCalClient> {
+    let token = OToken {
+        access: "test".to_string(),
+        scopes,
+        ..Default::default()
+    };
+    GCalClient::builder(token).base_url(base).build().unwrap()
+}
+
+fn event() -> Event {
+    let mut event = Event::default();
+    event.id = "event".to_string();
+    event.calendar_id = "primary".to_string();
+    event
+}
+
+#[tokio::test]
+async fn read_only_tokens_cannot_write() {
+    let (base, requests) = common::serve(respond).await;
+    let scopes = CalendarScopes::from([CalendarScope::CalendarReadonly]);
+    let (calendars, events) = client(&base, Some(scopes.clone())).clients();
+
+    match events.insert(event(), None).await {
+        Err(ClientError::InsufficientScope { required, granted }) => {
+            assert!(required.contains(&CalendarScope::Events));
+            assert_eq!(granted, scopes);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(matches!(
+        events.delete(event(), None).await,
+        Err(ClientError::InsufficientScope { .. })
+    ));
+    assert!(matches!(
+        events.patch(event(), None).await,
+        Err(ClientError::InsufficientScope { .. })
+    ));
+    assert!(requests.lock().unwrap().is_empty());
+
+    // Reading is still allowed.
+    events.list("primary".into(), None).await.unwrap();
+    calendars.list(None).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn granted_or_unknown_scopes_pass() {
+    let (base, requests) = common::serve(respond).await;
+
+    let events = client(&base, Some(CalendarScopes::from([CalendarScope::Events]))).event_client();
+    events.insert(event(), None).await.unwrap();
+    let events = client(&base, None).event_client();
+    events.insert(event(), None).await.unwrap();
+
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn unrelated_scopes_cannot_read_events() {
+    let (base, requests) = common::serve(respond).await;
+    let scopes = CalendarScopes::from([CalendarScope::UserInfoEmail, CalendarScope::OpenId]);
+    let events = client(&base, Some(scopes)).event_client();
+
+    assert!(matches!(
+        events.list("primary".into(), None).await,
+        Err(ClientError::InsufficientScope { .. })
+    ));
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn scopes_round_trip_their_url_form() {
+    for scope in [
+        CalendarScope::Calendar,
+        CalendarScope::CalendarReadonly,
+        CalendarScope::EventsOwnedReadonly,
+        CalendarScope::UserInfoEmail,
+        CalendarScope::OpenId,
+    ] {
+        assert_eq!(CalendarScope::from(scope.to_str()), scope);
+        let json = serde_json::to_value(&scope).unwrap();
+        assert_eq!(json, scope.to_str());
+        assert_eq!(
+            serde_json::from_value::<CalendarScope>(json).unwrap(),
+            scope
+        );
+    }
+    assert_eq!(
+        CalendarScope::Events.to_string(),
+        "https://www.googleapis.com/auth/calendar.events"
+    );
+    assert_eq!(
+        CalendarScope::from("https://www.googleapis.com/auth/tasks"),
+        CalendarScope::Unknown("https://www.googleapis.com/auth/tasks".to_string())
+    );
+}
+
+#[test]
+fn scope_lists_parse_google_aliases() {
+    let scopes = CalendarScopes::parse(
+        "openid email https://www.googleapis.com/auth/calendar.readonly profile email",
+    );
+    assert_eq!(
+        scopes.iter().cloned().collect::<Vec<_>>(),
+        [
+            CalendarScope::OpenId,
+            CalendarScope::UserInfoEmail,
+            CalendarScope::CalendarReadonly,
+            CalendarScope::UserInfoProfile,
+        ]
+    );
+
+    let json = serde_json::to_value(&scopes).unwrap();
+    assert_eq!(
+        json,
+        "openid https://www.googleapis.com/auth/userinfo.email \
+         https://www.googleapis.com/auth/calendar.readonly \
+         https://www.googleapis.com/auth/userinfo.profile"
+    );
+    assert_eq!(
+        serde_json::from_value::<CalendarScopes>(json).unwrap(),
+        scopes
+    );
+}
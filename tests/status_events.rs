@@ -0,0 +1,192 @@
+//! Out-of-office, focus-time and working-location events as Google sends and expects them.
+use chrono::NaiveDate;
+use serde_json::json;
+
+use gcal_rs::{types::*, *};
+
+mod common;
+
+fn date(d: u32) -> EventCalendarDate {
+    EventCalendarDate::from_date(NaiveDate::from_ymd_opt(2024, 3, d).unwrap())
+}
+
+fn round_trip(payload: serde_json::Value) -> (Event, serde_json::Value) {
+    let event: Event = serde_json::from_value(payload).unwrap();
+    let json = serde_json::to_value(&event).unwrap();
+    (event, json)
+}
+
+#[test]
+fn out_of_office_payload_round_trips() {
+    let properties = json!({
+        "autoDeclineMode": "declineOnlyNewConflictingInvitations",
+        "declineMessage": "On vacation"
+    });
+    let (event, json) = round_trip(json!({
+        "id": "ooo",
+        "eventType": "outOfOffice",
+        "start": {"date": "2024-03-04"},
+        "end": {"date": "2024-03-09"},
+        "outOfOfficeProperties": properties,
+    }));
+
+    assert_eq!(event.event_type, EventType::OutOfOffice);
+    let parsed = event.out_of_office_properties.unwrap();
+    assert_eq!(
+        parsed.auto_decline_mode,
+        Some(EventAutoDeclineMode::DeclineOnlyNewConflictingInvitations)
+    );
+    assert_eq!(parsed.decline_message.as_deref(), Some("On vacation"));
+    assert_eq!(json["eventType"], "outOfOffice");
+    assert_eq!(json["outOfOfficeProperties"], properties);
+}
+
+#[test]
+fn focus_time_payload_round_trips() {
+    let properties = json!({
+        "autoDeclineMode": "declineAllConflictingInvitations",
+        "declineMessage": "Heads down",
+        "chatStatus": "doNotDisturb"
+    });
+    let (event, json) = round_trip(json!({
+        "id": "focus",
+        "eventType": "focusTime",
+        "start": {"dateTime": "2024-03-04T09:00:00Z"},
+        "end": {"dateTime": "2024-03-04T11:00:00Z"},
+        "focusTimeProperties": properties,
+    }));
+
+    assert_eq!(event.event_type, EventType::FocusTime);
+    let parsed = event.focus_time_properties.unwrap();
+    assert_eq!(parsed.chat_status, Some(EventChatStatus::DoNotDisturb));
+    assert_eq!(json["eventType"], "focusTime");
+    assert_eq!(json["focusTimeProperties"], properties);
+}
+
+#[test]
+fn working_location_payloads_round_trip() {
+    let locations = [
+        json!({"type": "homeOffice", "homeOffice": {}}),
+        json!({"type": "officeLocation", "officeLocation": {"label": "HQ", "buildingId": "B1"}}),
+        json!({"type": "customLocation", "customLocation": {"label": "Café"}}),
+    ];
+    for location in locations {
+        let (event, json) = round_trip(json!({
+            "id": "where",
+            "eventType": "workingLocation",
+            "start": {"date": "2024-03-04"},
+            "end": {"date": "2024-03-05"},
+            "workingLocationProperties": location,
+        }));
+        assert_eq!(event.event_type, EventType::WorkingLocation);
+        assert_eq!(json["workingLocationProperties"], location);
+    }
+}
+
+#[test]
+fn constructors_build_the_expected_payloads() {
+    let ooo = Event::out_of_office(
+        date(4),
+        date(9),
+        EventAutoDeclineMode::DeclineAllConflictingInvitations,
+        Some("Back on Monday".to_string()),
+    );
+    let json = serde_json::to_value(&ooo).unwrap();
+    assert_eq!(json["eventType"], "outOfOffice");
+    assert_eq!(json["summary"], "Out of office");
+    assert_eq!(
+        json["outOfOfficeProperties"],
+        json!({
+            "autoDeclineMode": "declineAllConflictingInvitations",
+            "declineMessage": "Back on Monday"
+        })
+    );
+
+    let focus = Event::focus_time(
+        date(4),
+        date(5),
+        EventAutoDeclineMode::DeclineNone,
+        EventChatStatus::DoNotDisturb,
+    );
+    let json = serde_json::to_value(&focus).unwrap();
+    assert_eq!(json["eventType"], "focusTime");
+    assert_eq!(
+        json["focusTimeProperties"],
+        json!({"autoDeclineMode": "declineNone", "chatStatus": "doNotDisturb"})
+    );
+
+    let home = Event::working_location(date(4), date(5), EventWorkingLocation::home());
+    let json = serde_json::to_value(&home).unwrap();
+    assert_eq!(json["eventType"], "workingLocation");
+    assert_eq!(json["transparency"], "transparent");
+    assert_eq!(json["visibility"], "public");
+    assert_eq!(
+        json["workingLocationProperties"],
+        json!({"type": "homeOffice", "homeOffice": {}})
+    );
+    assert_eq!(
+        serde_json::to_value(EventWorkingLocation::office(Some("HQ".to_string()))).unwrap(),
+        json!({"type": "officeLocation", "officeLocation": {"label": "HQ"}})
+    );
+    assert_eq!(
+        serde_json::to_value(EventWorkingLocation::custom("Café")).unwrap(),
+        json!({"type": "customLocation", "customLocation": {"label": "Café"}})
+    );
+}
+
+#[tokio::test]
+async fn constructors_only_send_what_they_set() {
+    let (base, requests) = common::serve(|_, _| (200, r#"{"id": "event"}"#.to_string())).await;
+    let events = common::client(&base).event_client();
+
+    let ooo = Event::out_of_office(date(4), date(9), EventAutoDeclineMode::DeclineNone, None);
+    let focus = Event::focus_time(
+        date(4),
+        date(5),
+        EventAutoDeclineMode::DeclineNone,
+        EventChatStatus::DoNotDisturb,
+    );
+    let home = Event::working_location(date(4), date(5), EventWorkingLocation::home());
+    for mut event in [ooo, focus, home] {
+        event.calendar_id = "primary".to_string();
+        events.insert(event, None).await.unwrap();
+    }
+
+    let keys = |i: usize| {
+        let body = common::body(&requests.lock().unwrap()[i]);
+        let mut keys: Vec<_> = body.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    };
+    assert_eq!(
+        keys(0),
+        [
+            "end",
+            "eventType",
+            "outOfOfficeProperties",
+            "start",
+            "summary"
+        ]
+    );
+    assert_eq!(
+        keys(1),
+        [
+            "end",
+            "eventType",
+            "focusTimeProperties",
+            "start",
+            "summary"
+        ]
+    );
+    assert_eq!(
+        keys(2),
+        [
+            "end",
+            "eventType",
+            "start",
+            "transparency",
+            "visibility",
+            "workingLocationProperties"
+        ]
+    );
+}
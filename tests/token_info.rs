@@ -0,0 +1,152 @@
+//! Keeps what token responses say about a token and asks the tokeninfo endpoint about it.
+use std::time::{Duration, SystemTime};
+
+use gcal_rs::*;
+
+mod common;
+
+const TOKEN_INFO: &str = r#"{
+    "azp": "client.apps.googleusercontent.com",
+    "aud": "client.apps.googleusercontent.com",
+    "sub": "1234",
+    "scope": "https://www.googleapis.com/auth/calendar.readonly openid",
+    "exp": "1700003599",
+    "expires_in": "3599",
+    "email": "user@example.com",
+    "email_verified": "true",
+    "access_type": "offline"
+}"#;
+
+/// Describe the `access` token, exchange codes for a token without scopes but with an ID token.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("POST /tokeninfo ") && request.ends_with("\r\n\r\naccess_token=access") {
+        (200, TOKEN_INFO.to_string())
+    } else if request.starts_with("POST /tokeninfo") {
+        (
+            400,
+            r#"{"error": "invalid_token", "error_description": "Invalid Value"}"#.to_string(),
+        )
+    } else {
+        (
+            200,
+            r#"{
+                "access_token": "access",
+                "refresh_token": "refresh",
+                "expires_in": 3599,
+                "token_type": "Bearer",
+                "id_token": "header.claims.signature"
+            }"#
+            .to_string(),
+        )
+    }
+}
+
+#[tokio::test]
+async fn token_info_describes_the_access_token() {
+    let (base, requests) = common::serve(respond).await;
+    let oauth = common::oauth(&base);
+    let token = OToken {
+        access: "access".to_string(),
+        ..Default::default()
+    };
+
+    let info = oauth.token_info(&token).await.unwrap();
+    assert_eq!(info.audience, "client.apps.googleusercontent.com");
+    assert_eq!(info.user_id.as_deref(), Some("1234"));
+    assert_eq!(info.email.as_deref(), Some("user@example.com"));
+    assert!(info.email_verified);
+    assert_eq!(
+        info.scopes,
+        CalendarScopes::from([CalendarScope::CalendarReadonly, CalendarScope::OpenId])
+    );
+    assert_eq!(info.expires_in, Some(Duration::from_secs(3599)));
+    assert_eq!(info.access_type.as_deref(), Some("offline"));
+
+    let revoked = OToken {
+        access: "revoked".to_string(),
+        ..Default::default()
+    };
+    let err = oauth.token_info(&revoked).await.unwrap_err();
+    assert!(err.to_string().contains("invalid_token"), "{}", err);
+
+    // The token never appears in the URL.
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in requests.iter() {
+        assert!(
+            request.starts_with("POST /tokeninfo HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        assert!(request
+            .to_lowercase()
+            .contains("content-type: application/x-www-form-urlencoded"));
+    }
+}
+
+#[tokio::test]
+async fn token_info_uses_the_shared_http_client() {
+    let (base, requests) = common::serve(respond).await;
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-shared", reqwest::header::HeaderValue::from_static("1"));
+    let http = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    let token = OToken {
+        access: "access".to_string(),
+        ..Default::default()
+    };
+
+    let oauth = common::oauth(&base).with_http_client(http);
+    oauth.token_info(&token).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].to_lowercase().contains("x-shared: 1"));
+}
+
+#[tokio::test]
+async fn exchanged_tokens_keep_type_id_token_and_redirect_scopes() {
+    let (base, _) = common::serve(respond).await;
+    let oauth = common::oauth(&base);
+    let loopback = oauth.loopback(LoopbackOptions::default()).await.unwrap();
+
+    let state = url::Url::parse(loopback.auth_url())
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == "state")
+        .map(|(_, v)| v.into_owned())
+        .unwrap();
+    let redirect = format!(
+        "{}?code=abc&state={}&scope=email%20https://www.googleapis.com/auth/calendar",
+        loopback.redirect_url(),
+        state
+    );
+    let browser = tokio::spawn(reqwest::get(redirect));
+    let token = loopback.wait().await.unwrap();
+    browser.await.unwrap().unwrap();
+
+    assert_eq!(token.token_type.as_deref(), Some("bearer"));
+    assert_eq!(token.id_token.as_deref(), Some("header.claims.signature"));
+    assert!(token.has_scope(&CalendarScope::Calendar));
+    assert!(token.has_scope(&CalendarScope::UserInfoEmail));
+    assert!(!token.has_scope(&CalendarScope::OpenId));
+
+    let expires_in = token.expires_in().unwrap();
+    assert!(expires_in > Duration::from_secs(3500) && expires_in <= Duration::from_secs(3539));
+}
+
+#[test]
+fn expires_in_counts_down_to_zero() {
+    let mut token = OToken::default();
+    assert_eq!(token.expires_in(), None);
+
+    token.expires_at = Some(SystemTime::now() + Duration::from_secs(120));
+    let left = token.expires_in().unwrap();
+    assert!(left > Duration::from_secs(110) && left <= Duration::from_secs(120));
+    assert!(!token.is_expired());
+
+    token.expires_at = Some(SystemTime::now() - Duration::from_secs(1));
+    assert_eq!(token.expires_in(), Some(Duration::ZERO));
+    assert!(token.is_expired());
+}
@@ -0,0 +1,140 @@
+//! Refreshes tokens before and after Google rejects them.
+use std::{sync::Arc, time::SystemTime};
+
+use gcal_rs::*;
+
+mod common;
+
+const EVENTS: &str = r#"{"items": [{"id": "event"}]}"#;
+const FRESH: &str = r#"{"access_token": "fresh", "expires_in": 3599, "token_type": "Bearer"}"#;
+
+/// Reject every access token but `fresh`, which refreshes hand out.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("POST /token") {
+        (200, FRESH.to_string())
+    } else if request.contains("Bearer fresh") {
+        (200, EVENTS.to_string())
+    } else {
+        (
+            401,
+            r#"{"error": {"code": 401, "message": "Invalid Credentials"}}"#.to_string(),
+        )
+    }
+}
+
+/// Fail the first refresh, then refresh without a new refresh token.
+fn flaky(request: &str, seen: usize) -> (u16, String) {
+    match (request.starts_with("POST /token"), seen) {
+        (true, 0) => (500, r#"{"error": "internal_failure"}"#.to_string()),
+        (true, _) => (200, FRESH.to_string()),
+        (false, _) => respond(request, seen),
+    }
+}
+
+/// Reject every access token.
+fn reject(request: &str, seen: usize) -> (u16, String) {
+    match request.starts_with("POST /token") {
+        true => respond(request, seen),
+        false => (401, "{}".to_string()),
+    }
+}
+
+/// A token that looks valid, or has expired.
+fn token(expired: bool) -> OToken {
+    OToken {
+        access: "stale".to_string(),
+        refresh: Some("refresh".to_string()),
+        expires_at: expired.then_some(SystemTime::UNIX_EPOCH),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn rejected_tokens_are_renewed_and_retried_once() {
+    let (base, requests) = common::serve(respond).await;
+    let client = GCalClient::builder(token(false))
+        .oauth(Arc::new(common::oauth(&base)))
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    let events = client.clone().event_client();
+    let list = events.list("primary".into(), None).await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(client.token().await.access, "fresh");
+    assert_eq!(common::count(&requests, "GET /calendars"), 2);
+    assert_eq!(common::count(&requests, "POST /token"), 1);
+}
+
+#[tokio::test]
+async fn concurrent_rejections_renew_once() {
+    let (base, requests) = common::serve(respond).await;
+    let client = GCalClient::builder(token(false))
+        .oauth(Arc::new(common::oauth(&base)))
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let events = client.clone().event_client();
+            tokio::spawn(async move { events.list("primary".into(), None).await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+    assert_eq!(common::count(&requests, "POST /token"), 1);
+}
+
+#[tokio::test]
+async fn tokens_rejected_twice_are_invalid() {
+    let (base, requests) = common::serve(reject).await;
+    let client = GCalClient::builder(token(false))
+        .oauth(Arc::new(common::oauth(&base)))
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    let err = client
+        .event_client()
+        .list("primary".into(), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClientError::InvalidToken), "{}", err);
+    assert_eq!(common::count(&requests, "GET /calendars"), 2);
+    assert_eq!(common::count(&requests, "POST /token"), 1);
+}
+
+#[tokio::test]
+async fn rejected_tokens_without_oauth_are_invalid() {
+    let (base, requests) = common::serve(respond).await;
+    let client = GCalClient::builder(token(false))
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    let err = client
+        .event_client()
+        .list("primary".into(), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClientError::InvalidToken), "{}", err);
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn failed_refreshes_keep_the_refresh_token() {
+    let (base, _) = common::serve(flaky).await;
+    let oauth = Arc::new(common::oauth(&base));
+    let mut token = token(true);
+
+    assert!(oauth.refresh(&mut token).await.is_err());
+    assert_eq!(token.refresh.as_deref(), Some("refresh"));
+
+    // The response has no refresh token, the old one stays.
+    oauth.refresh(&mut token).await.unwrap();
+    assert_eq!(token.access, "fresh");
+    assert_eq!(token.refresh.as_deref(), Some("refresh"));
+    assert!(!token.is_expired());
+}
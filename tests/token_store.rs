@@ -0,0 +1,243 @@
+//! Saves and restores tokens, and persists tokens refreshed by the client.
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use gcal_rs::*;
+
+mod common;
+
+const EVENTS: &str = r#"{"items": [{"id": "event"}]}"#;
+
+/// Answer refreshes on `/token` and everything else with an event listing.
+fn respond(request: &str, _: usize) -> (u16, String) {
+    if request.starts_with("POST /token") {
+        (
+            200,
+            r#"{"access_token": "fresh", "expires_in": 3599, "token_type": "Bearer"}"#.to_string(),
+        )
+    } else {
+        (200, EVENTS.to_string())
+    }
+}
+
+fn token() -> OToken {
+    OToken {
+        access: "access".to_string(),
+        refresh: Some("refresh".to_string()),
+        scopes: Some(CalendarScopes::from([CalendarScope::Calendar])),
+        expires_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+        token_type: Some("bearer".to_string()),
+        id_token: Some("header.claims.signature".to_string()),
+    }
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("gcal_rs-{}-{}", name, std::process::id()))
+        .join("token.json")
+}
+
+#[test]
+fn file_store_round_trips() {
+    let path = temp_path("round-trip");
+    let store = FileTokenStore::new(&path);
+    assert_eq!(store.load().unwrap(), None);
+
+    store.save(&token()).unwrap();
+    assert_eq!(store.load().unwrap(), Some(token()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    store.clear().unwrap();
+    assert_eq!(store.load().unwrap(), None);
+    store.clear().unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn file_store_rejects_garbage() {
+    let path = temp_path("garbage");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "not a token").unwrap();
+
+    let err = FileTokenStore::new(&path).load().unwrap_err();
+    assert!(matches!(err, ClientError::TokenStoreError(_)), "{}", err);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn memory_store_round_trips() {
+    let store = MemoryTokenStore::new();
+    assert_eq!(store.load().unwrap(), None);
+    store.save(&token()).unwrap();
+    assert_eq!(store.load().unwrap(), Some(token()));
+    store.clear().unwrap();
+    assert_eq!(store.load().unwrap(), None);
+}
+
+#[tokio::test]
+async fn refreshed_tokens_are_saved() {
+    let (base, requests) = common::serve(respond).await;
+    let store = Arc::new(MemoryTokenStore::with_token(token()));
+
+    let client = GCalClient::builder(store.load().unwrap().unwrap())
+        .oauth(Arc::new(common::oauth(&base)))
+        .token_store(store.clone())
+        .base_url(&base)
+        .build()
+        .unwrap();
+    let events = client.clone().event_client();
+    events.list("primary".into(), None).await.unwrap();
+
+    let saved = store.load().unwrap().unwrap();
+    assert_eq!(saved.access, "fresh");
+    assert!(!saved.is_expired());
+    assert!(requests.lock().unwrap()[1].contains("Bearer fresh"));
+
+    // A valid token is neither refreshed nor saved again.
+    store.clear().unwrap();
+    events.list("primary".into(), None).await.unwrap();
+    assert_eq!(store.load().unwrap(), None);
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+/// A store whose saves wait until they are released.
+#[derive(Debug)]
+struct SlowStore {
+    inner: MemoryTokenStore,
+    release: Mutex<mpsc::Receiver<()>>,
+}
+
+impl TokenStore for SlowStore {
+    fn load(&self) -> ClientResult<Option<OToken>> {
+        self.inner.load()
+    }
+
+    fn save(&self, token: &OToken) -> ClientResult<()> {
+        let _ = self
+            .release
+            .lock()
+            .unwrap()
+            .recv_timeout(Duration::from_secs(10));
+        self.inner.save(token)
+    }
+
+    fn clear(&self) -> ClientResult<()> {
+        self.inner.clear()
+    }
+}
+
+#[tokio::test]
+async fn slow_saves_do_not_hold_up_requests() {
+    let (base, _) = common::serve(respond).await;
+    let (release, receiver) = mpsc::channel();
+    let store = Arc::new(SlowStore {
+        inner: MemoryTokenStore::with_token(token()),
+        release: Mutex::new(receiver),
+    });
+    let client = GCalClient::builder(token())
+        .oauth(Arc::new(common::oauth(&base)))
+        .token_store(store.clone())
+        .base_url(&base)
+        .build()
+        .unwrap();
+
+    let refreshing = tokio::spawn({
+        let client = client.clone();
+        async move { client.refresh().await }
+    });
+    // The renewed token is usable while the store is still saving it.
+    let started = Instant::now();
+    while client.token().await.access != "fresh" {
+        assert!(started.elapsed() < Duration::from_secs(5));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(store.load().unwrap().unwrap().access, "access");
+
+    release.send(()).unwrap();
+    refreshing.await.unwrap().unwrap();
+    assert_eq!(store.load().unwrap().unwrap().access, "fresh");
+}
+
+#[test]
+fn encrypted_store_seals_the_token() {
+    let path = temp_path("sealed");
+    let store = EncryptedFileTokenStore::new(&path, TokenKey::new("k1", [7; 32]));
+    assert_eq!(store.load().unwrap(), None);
+
+    store.save(&token()).unwrap();
+    let file = std::fs::read_to_string(&path).unwrap();
+    assert!(!file.contains("refresh"), "{}", file);
+    assert!(file.contains("\"k1\""));
+    assert_eq!(store.load().unwrap(), Some(token()));
+
+    let wrong = EncryptedFileTokenStore::new(&path, TokenKey::new("k1", [8; 32]));
+    assert!(matches!(wrong.load(), Err(ClientError::TokenStoreError(_))));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn encrypted_store_refuses_tampered_files() {
+    let path = temp_path("tampered");
+    let store = EncryptedFileTokenStore::new(&path, TokenKey::new("k1", [7; 32]));
+    store.save(&token()).unwrap();
+    let sealed: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+    // Flip a bit of the ciphertext.
+    let mut tampered = sealed.clone();
+    let ciphertext = sealed["ciphertext"].as_str().unwrap();
+    let flipped = if ciphertext.starts_with('A') {
+        "B"
+    } else {
+        "A"
+    };
+    tampered["ciphertext"] = format!("{}{}", flipped, &ciphertext[1..]).into();
+    std::fs::write(&path, tampered.to_string()).unwrap();
+    let err = store.load().unwrap_err();
+    assert!(err.to_string().contains("modified"), "{}", err);
+
+    // Claim another known key sealed it.
+    let mut relabeled = sealed.clone();
+    relabeled["key_id"] = "k0".into();
+    std::fs::write(&path, relabeled.to_string()).unwrap();
+    let store = store.old_key(TokenKey::new("k0", [7; 32]));
+    assert!(store.load().is_err());
+
+    std::fs::write(&path, sealed.to_string()).unwrap();
+    assert_eq!(store.load().unwrap(), Some(token()));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn encrypted_store_rotates_keys() {
+    let path = temp_path("rotate");
+    let old = TokenKey::generate("2023");
+    let new = TokenKey::from_passphrase("2024", "correct horse battery staple");
+    EncryptedFileTokenStore::new(&path, old.clone())
+        .save(&token())
+        .unwrap();
+
+    let unknown = EncryptedFileTokenStore::new(&path, new.clone()).load();
+    assert!(unknown
+        .unwrap_err()
+        .to_string()
+        .contains("unknown key `2023`"));
+
+    let store = EncryptedFileTokenStore::new(&path, new.clone()).old_key(old.clone());
+    assert_eq!(store.load().unwrap(), Some(token()));
+    assert!(store.rotate().unwrap());
+    assert!(!store.rotate().unwrap());
+
+    let rotated = EncryptedFileTokenStore::new(&path, new);
+    assert_eq!(rotated.load().unwrap(), Some(token()));
+    assert!(EncryptedFileTokenStore::new(&path, old).load().is_err());
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}